@@ -0,0 +1,85 @@
+//! Compares the dynamic route matcher against the previous linear scan.
+//!
+//! Run with `cargo bench --bench router`.
+
+#[allow(dead_code)]
+#[path = "../src/router/trie.rs"]
+mod trie;
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use trie::PathTrie;
+
+const ROUTES: usize = 500;
+const ITERATIONS: usize = 2_000;
+
+// The matcher used before the trie: every pattern is compared in turn
+fn match_dynamic_path(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+    let pattern_parts: Vec<&str> = pattern.split('/').collect();
+    let path_parts: Vec<&str> = path.split('/').collect();
+
+    if pattern_parts.len() != path_parts.len() {
+        return None;
+    }
+
+    let mut params = HashMap::new();
+    for (pattern_part, path_part) in pattern_parts.iter().zip(path_parts.iter()) {
+        if let Some(name) = pattern_part.strip_prefix(':') {
+            params.insert(name.to_string(), path_part.to_string());
+        } else if pattern_part != path_part {
+            return None;
+        }
+    }
+    Some(params)
+}
+
+fn linear(patterns: &[String], path: &str) -> Option<HashMap<String, String>> {
+    patterns.iter().find_map(|pattern| match_dynamic_path(pattern, path))
+}
+
+fn measure(name: &str, paths: &[String], mut lookup: impl FnMut(&str)) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for path in paths {
+            lookup(path);
+        }
+    }
+    let elapsed = start.elapsed();
+    let per_lookup = elapsed / (ITERATIONS * paths.len()) as u32;
+    println!("{:<8} {:>10.2?} total, {:>8.2?} per lookup", name, elapsed, per_lookup);
+    elapsed
+}
+
+fn main() {
+    let patterns: Vec<String> = (0..ROUTES)
+        .map(|i| format!("/api/resource{}/:id/items/:item_id", i))
+        .collect();
+    let mut trie = PathTrie::default();
+    for pattern in &patterns {
+        trie.insert(pattern);
+    }
+
+    // First, middle and last routes plus a miss
+    let paths: Vec<String> = [0, ROUTES / 2, ROUTES - 1]
+        .iter()
+        .map(|i| format!("/api/resource{}/42/items/7", i))
+        .chain(std::iter::once("/api/missing/42/items/7".to_string()))
+        .collect();
+
+    for path in &paths {
+        assert_eq!(
+            linear(&patterns, path),
+            trie.matches(path).into_iter().next().map(|(_, params)| params)
+        );
+    }
+
+    println!("{} routes, {} lookups each", ROUTES, ITERATIONS * paths.len());
+    let old = measure("linear", &paths, |path| {
+        black_box(linear(&patterns, black_box(path)));
+    });
+    let new = measure("trie", &paths, |path| {
+        black_box(trie.matches(black_box(path)));
+    });
+    println!("speedup  {:.1}x", old.as_secs_f64() / new.as_secs_f64());
+}
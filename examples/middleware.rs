@@ -17,7 +17,7 @@ impl Middleware for Logger {
         Box::pin(async move {
             let start = Instant::now();
             let url = req.path.clone();
-            let method = req.method.clone();
+            let method = req.method;
             let res = next.handle(req).await;
             let status = match &res {
                 Ok(res) => res.status,
@@ -43,7 +43,10 @@ impl Middleware for AuthMiddleware {
             // Check for token in Authorization header
             match req.get_header("Authorization") {
                 Some(token) if token.starts_with("Bearer ") => next.handle(req).await,
-                _ => Err(ServerError::Unauthorized("Authentication required".to_string())),
+                _ => Err(ServerError::Unauthorized {
+                    message: "Authentication required".to_string(),
+                    challenge: "Bearer".to_string(),
+                }),
             }
         })
     }
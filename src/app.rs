@@ -5,8 +5,8 @@
 //!
 //! # Examples
 //!
-//! ```rust
-//! use axeon::{Server, ok_json};
+//! ```rust,no_run
+//! use axeon::{Response, Server, ok_json};
 //!
 //! fn main() {
 //!     let mut app = Server::new();
@@ -17,16 +17,21 @@
 //! }
 //! ```
 
-use crate::error::ServerError;
-use crate::handler::{HttpResponse, IntoResponse};
-use crate::http::{Body, Method, Request};
+use crate::config::ServerSettings;
+use crate::error::{ServerError, SetupError};
+use crate::handler::{Handler, HttpResponse, IntoResponse};
+use crate::http::{Body, ClientCertInfo, Method, Request};
 use crate::http::Response;
-use crate::middleware::Middleware;
+use crate::http::response::{content_type_for, BodyStream};
+use crate::http::timing::ServerTiming;
+use crate::middleware::{Middleware, MiddlewareManager, Next};
 use crate::plugins::Plugins;
-use crate::router::{Route, Router};
-use futures::{FutureExt};
+use crate::router::{Route, RouteDef, Router, TrailingSlashMode};
+use futures::{FutureExt, StreamExt};
 use std::collections::HashMap;
-use std::io::{Error, ErrorKind};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::io::{Error, ErrorKind, Seek, SeekFrom};
 use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -36,30 +41,94 @@ use std::fs;
 use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
 use tokio::net::{TcpListener};
 use tokio::runtime::Runtime;
-use rustls::ServerConfig;
+use tokio::sync::{mpsc, watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{timeout, timeout_at, Instant};
+#[cfg(feature = "tls")]
+use rustls::server::WebPkiClientVerifier;
+#[cfg(feature = "tls")]
+use rustls::{RootCertStore, ServerConfig};
+#[cfg(feature = "tls")]
 use tokio_rustls::TlsAcceptor;
+#[cfg(feature = "tls")]
 use std::fs::File;
+#[cfg(feature = "tls")]
 use std::io::BufReader as StdBufReader;
+#[cfg(feature = "tls")]
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 
 type ErrorHandler = Arc<dyn Fn(ServerError) -> Response + Send + Sync>;
+type ConnectionErrorHandler = Arc<dyn Fn(&Error) + Send + Sync>;
+type RewriteHook = Arc<dyn Fn(&mut Request) + Send + Sync>;
+type PanicHandler = Arc<dyn Fn(&str) + Send + Sync>;
+
+// Holds a connection's place in the limit and the active count until the
+// connection task ends or is cancelled
+struct ConnectionSlot {
+    _permit: OwnedSemaphorePermit,
+    active: Arc<AtomicUsize>,
+}
+
+impl ConnectionSlot {
+    fn new(permit: OwnedSemaphorePermit, active: Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::Relaxed);
+        Self { _permit: permit, active }
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
 
 /// TLS configuration for HTTPS support
+#[cfg(feature = "tls")]
+#[derive(Clone)]
 pub struct TlsConfig {
     cert_file: PathBuf,
     key_file: PathBuf,
+    client_ca_file: Option<PathBuf>,
 }
 
+#[cfg(feature = "tls")]
 impl TlsConfig {
     pub fn new<P: AsRef<Path>>(cert_file: P, key_file: P) -> Self {
         Self {
             cert_file: cert_file.as_ref().to_path_buf(),
             key_file: key_file.as_ref().to_path_buf(),
+            client_ca_file: None,
         }
     }
 
-    fn load_certs(&self) -> Result<Vec<CertificateDer<'static>>, Box<dyn std::error::Error>> {
-        let cert_file = File::open(&self.cert_file)?;
+    /// Requires clients to present a certificate signed by a CA in `ca_file`
+    pub fn with_client_auth<P: AsRef<Path>>(mut self, ca_file: P) -> Self {
+        self.client_ca_file = Some(ca_file.as_ref().to_path_buf());
+        self
+    }
+
+    fn server_config(&self) -> Result<ServerConfig, Box<dyn std::error::Error>> {
+        let certs = Self::load_certs(&self.cert_file)?;
+        let key = self.load_key()?;
+        let builder = ServerConfig::builder();
+        let config = match &self.client_ca_file {
+            Some(ca_file) => {
+                let mut roots = RootCertStore::empty();
+                for cert in Self::load_certs(ca_file)? {
+                    roots.add(cert)?;
+                }
+                let verifier = WebPkiClientVerifier::builder(Arc::new(roots)).build()?;
+                builder
+                    .with_client_cert_verifier(verifier)
+                    .with_single_cert(certs, key)?
+            }
+            None => builder.with_no_client_auth().with_single_cert(certs, key)?,
+        };
+        Ok(config)
+    }
+
+    fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, Box<dyn std::error::Error>> {
+        let cert_file = File::open(path)?;
         let mut reader = StdBufReader::new(cert_file);
         let certs = rustls_pemfile::certs(&mut reader)
             .filter_map(|result| result.ok())
@@ -71,7 +140,7 @@ impl TlsConfig {
         let key_file = File::open(&self.key_file)?;
         let mut reader = StdBufReader::new(key_file);
         let key = rustls_pemfile::private_key(&mut reader)?
-            .ok_or_else(|| "No private key found")?;
+            .ok_or("No private key found")?;
         Ok(key)
     }
 }
@@ -80,8 +149,8 @@ impl TlsConfig {
 ///
 /// # Example
 ///
-/// ```rust
-/// use axeon::{ok_json, Server};
+/// ```rust,no_run
+/// use axeon::{ok_json, Response, Server};
 ///
 ///
 /// fn main() {
@@ -103,37 +172,198 @@ impl TlsConfig {
 pub struct Server {
     pub max_connections: usize,
     pub keep_alive: Duration,
+    pub request_timeout: Duration,
+    pub drain_timeout: Duration,
+    max_body_size: usize,
+    in_flight_limit: Option<Arc<Semaphore>>,
+    reject_when_busy: bool,
+    active_connections: Arc<AtomicUsize>,
+    setup_errors: Vec<Arc<dyn std::error::Error + Send + Sync>>,
     router: Router,
+    routers: Vec<Router>,
+    global_middlewares: MiddlewareManager,
+    fallback: Option<Box<dyn Handler>>,
+    rewrites: Vec<RewriteHook>,
+    trailing_slash: Option<TrailingSlashMode>,
+    default_content_type: Option<String>,
+    sniff_content_type: bool,
     static_dir: Option<PathBuf>,
+    static_stream_threshold: u64,
     plugins: Plugins,
     on_error: Option<ErrorHandler>,
+    on_connection_error: ConnectionErrorHandler,
+    on_accept_error: ConnectionErrorHandler,
+    on_panic: PanicHandler,
+    expose_panic_messages: bool,
+    #[cfg(feature = "tls")]
     tls_config: Option<Arc<TlsConfig>>,
 }
 
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Server {
     /// Creates a new Application instance
     pub fn new() -> Self {
         Self {
             max_connections: 256,
             keep_alive: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(30),
+            drain_timeout: Duration::from_secs(10),
+            max_body_size: 2 * 1024 * 1024,
+            in_flight_limit: None,
+            reject_when_busy: false,
+            active_connections: Arc::new(AtomicUsize::new(0)),
+            setup_errors: Vec::new(),
             router: Router::new(),
+            routers: Vec::new(),
+            global_middlewares: MiddlewareManager::new(),
+            fallback: None,
+            rewrites: Vec::new(),
+            trailing_slash: None,
+            default_content_type: None,
+            sniff_content_type: false,
             static_dir: None,
+            static_stream_threshold: 1024 * 1024,
             plugins: Plugins::new(),
             on_error: None,
+            on_connection_error: Arc::new(|err| eprintln!("Connection error: {}", err)),
+            on_accept_error: Arc::new(|err| eprintln!("Connection failed: {}", err)),
+            on_panic: Arc::new(|msg| eprintln!("Handler panicked: {}", msg)),
+            expose_panic_messages: false,
+            #[cfg(feature = "tls")]
             tls_config: None,
         }
     }
 
+    /// Sets how many connections are served at once. Once the limit is
+    /// reached the server stops accepting until a connection closes; new
+    /// clients wait in the listen backlog meanwhile.
+    ///
+    /// ```rust
+    /// use axeon::Server;
+    ///
+    /// let mut app = Server::new();
+    /// app.max_connections(512);
+    /// assert_eq!(app.max_connections, 512);
+    /// ```
     pub fn max_connections(&mut self, max_connections: usize) -> &mut Self {
         self.max_connections = max_connections;
         self
     }
 
+    /// Returns the live count of open connections. Take it before calling
+    /// `listen` and hand it to handlers or monitoring; it goes up when a
+    /// connection is accepted and down when it closes.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    /// use std::sync::atomic::Ordering;
+    ///
+    /// let mut app = Server::new();
+    /// let active = app.active_connections();
+    /// app.get("/metrics", move |_req| {
+    ///     let active = active.load(Ordering::Relaxed);
+    ///     async move { Response::text(format!("active_connections {}", active)) }
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn active_connections(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.active_connections)
+    }
+
+    /// Limits how many requests are handled at once across all connections,
+    /// unlike `max_connections` which counts sockets. By default requests
+    /// over the limit wait for a free slot until their `request_timeout`
+    /// expires; see `reject_when_busy` to answer them with 503 instead.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.max_in_flight(64);
+    /// app.get("/report", |_req| async { Response::text("done") });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn max_in_flight(&mut self, limit: usize) -> &mut Self {
+        self.in_flight_limit = Some(Arc::new(Semaphore::new(limit)));
+        self
+    }
+
+    /// Answers requests over the `max_in_flight` limit with 503 right away
+    /// instead of letting them wait for a free slot
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.max_in_flight(64).reject_when_busy(true);
+    /// app.get("/report", |_req| async { Response::text("done") });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn reject_when_busy(&mut self, reject: bool) -> &mut Self {
+        self.reject_when_busy = reject;
+        self
+    }
+
     pub fn keep_alive(&mut self, keep_alive: Duration) -> &mut Self {
         self.keep_alive = keep_alive;
         self
     }
 
+    /// Sets the maximum time allowed to read a request's headers and body
+    /// and run its handler. Requests exceeding it get a 504 response.
+    pub fn request_timeout(&mut self, request_timeout: Duration) -> &mut Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Sets the maximum size in bytes of a request body (default 2 MiB).
+    /// Requests declaring a larger `Content-Length` get a 413 response
+    /// before any of the body is read, and without a `100 Continue`.
+    /// Requests with a `Content-Encoding: gzip` or `deflate` body that
+    /// inflates past it get a 413 response as well.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.max_body_size(16 * 1024 * 1024);
+    /// app.post("/upload", |req| async move { Response::text(req.body.as_bytes().len().to_string()) });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn max_body_size(&mut self, max_body_size: usize) -> &mut Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Sets how long in-flight connections may keep running after shutdown
+    /// is requested before they are cancelled.
+    pub fn drain_timeout(&mut self, drain_timeout: Duration) -> &mut Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    /// Sets the content type assumed for request bodies sent without a
+    /// Content-Type header
+    pub fn default_content_type(&mut self, content_type: &str) -> &mut Self {
+        self.default_content_type = Some(content_type.to_string());
+        self
+    }
+
+    /// When enabled, request bodies sent without a Content-Type header that
+    /// parse as JSON are treated as `application/json`
+    pub fn sniff_content_type(&mut self, enabled: bool) -> &mut Self {
+        self.sniff_content_type = enabled;
+        self
+    }
+
     pub fn plugins<T>(&mut self, plugin: T) -> &mut Self
     where
         T: Send + Sync + 'static,
@@ -150,6 +380,44 @@ impl Server {
         self
     }
 
+    /// Sets the callback invoked when an established connection fails
+    /// (TLS handshake, read or write errors). Defaults to logging to stderr.
+    pub fn on_connection_error<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&Error) + Send + Sync + 'static,
+    {
+        self.on_connection_error = Arc::new(handler);
+        self
+    }
+
+    /// Sets the callback invoked when accepting a new connection fails.
+    /// Defaults to logging to stderr.
+    pub fn on_accept_error<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&Error) + Send + Sync + 'static,
+    {
+        self.on_accept_error = Arc::new(handler);
+        self
+    }
+
+    /// Sets the callback that receives the full message when a handler
+    /// panics. Defaults to logging to stderr.
+    pub fn on_panic<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_panic = Arc::new(handler);
+        self
+    }
+
+    /// Controls whether panic messages are included in the 500 response
+    /// sent to clients. Off by default, so clients get a plain-text
+    /// `Internal Server Error` and the message only reaches `on_panic`.
+    pub fn expose_panic_messages(&mut self, expose: bool) -> &mut Self {
+        self.expose_panic_messages = expose;
+        self
+    }
+
     /// Registers a GET route handler
     ///
     /// # Arguments
@@ -243,9 +511,23 @@ impl Server {
 
     /// Registers an OPTIONS route handler
     ///
+    /// Without one, OPTIONS requests to a routed path get a 204 with an
+    /// `Allow` header listing every method registered for it.
+    ///
     /// # Arguments
     /// * `path` - The URL path to match
     /// * `handler` - The async handler function
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.post("/items", |_req| async { Response::text("created") });
+    /// app.delete("/items", |_req| async { Response::text("deleted") });
+    /// // OPTIONS /items gets a 204 with `Allow: POST, DELETE, OPTIONS`
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
     pub fn options<F, R>(&mut self, path: &str, handler: F)
     where
         F: Fn(Request) -> R + Send + Clone + Sync + 'static,
@@ -267,7 +549,71 @@ impl Server {
         self.router.trace(path, handler);
     }
 
-    /// Adds a middleware to the application
+    /// Registers every route in a declarative table
+    ///
+    /// # Arguments
+    /// * `routes` - Route entries created with `axeon::route`
+    pub fn register<I>(&mut self, routes: I) -> &mut Self
+    where
+        I: IntoIterator<Item = RouteDef>,
+    {
+        self.router.register(routes);
+        self
+    }
+
+    /// Sets a timeout for one route's handler, replacing the server's
+    /// `request_timeout` for it. Can be called before or after the route
+    /// is registered.
+    ///
+    /// # Arguments
+    /// * `method` - The method the route is registered with
+    /// * `path` - The route's path
+    /// * `timeout` - Maximum time the handler may run
+    ///
+    /// # Example
+    /// ```rust
+    /// use axeon::{Method, Response, Server};
+    /// use std::time::Duration;
+    ///
+    /// let mut app = Server::new();
+    /// app.request_timeout(Duration::from_secs(5));
+    /// app.get("/reports/annual", |_req| async { Response::text("report") });
+    /// app.route_timeout(Method::GET, "/reports/annual", Duration::from_secs(60));
+    /// ```
+    pub fn route_timeout(&mut self, method: Method, path: &str, timeout: Duration) -> &mut Self {
+        self.router.route_timeout(method, path, timeout);
+        self
+    }
+
+    /// Adds a hook that can modify each request before route matching,
+    /// e.g. to rewrite legacy paths. Hooks run in registration order.
+    ///
+    /// # Arguments
+    /// * `hook` - Function that mutates the incoming request
+    ///
+    /// # Example
+    /// ```rust
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.rewrite(|req| {
+    ///     if req.path == "/old" {
+    ///         req.path = "/new".to_string();
+    ///     }
+    /// });
+    /// app.get("/new", |_req| async { Response::text("new") });
+    /// ```
+    pub fn rewrite<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&mut Request) + Send + Sync + 'static,
+    {
+        self.rewrites.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a middleware to the application. It runs for matched routes,
+    /// static files and the fallback, but not for requests answered with a
+    /// plain 404 or 405; use `global_middleware` for those.
     ///
     /// # Arguments
     /// * `middleware` - The middleware to add
@@ -275,6 +621,102 @@ impl Server {
         self.router.middleware(middleware);
     }
 
+    /// Adds a middleware that wraps every request, including ones that
+    /// match no route, after `rewrite` hooks have run. Global middleware
+    /// runs before, and around, the middleware added with `middleware`.
+    ///
+    /// ```rust,no_run
+    /// use axeon::middleware::{AccessLog, LogFormat};
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// // Logs requests answered with a plain 404 or 405 too
+    /// app.global_middleware(AccessLog::new(LogFormat::Combined));
+    /// app.get("/hello", |_req| async { Response::text("hi") });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn global_middleware(&mut self, middleware: impl Middleware + 'static) -> &mut Self {
+        self.global_middlewares.add(middleware);
+        self
+    }
+
+    /// Treats `/users` and `/users/` as distinct routes when `strict` is
+    /// true, see `Router::strict_slash`. Call before registering routes:
+    /// paths are normalized as they are registered, so changing it
+    /// afterwards is reported as a setup error by `build` and `listen`.
+    ///
+    /// ```rust
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/users/", |_req| async { Response::text("users") });
+    /// app.strict_slash(true);
+    /// assert!(app.build().is_err());
+    /// ```
+    pub fn strict_slash(&mut self, strict: bool) -> &mut Self {
+        if strict != self.router.is_strict_slash() && !self.router.routes.is_empty() {
+            let err: Box<dyn std::error::Error + Send + Sync> =
+                "strict_slash must be set before registering routes".into();
+            self.setup_errors.push(Arc::from(err));
+            return self;
+        }
+        self.router.strict_slash(strict);
+        self
+    }
+
+    /// Enforces a canonical form for trailing slashes. The path is checked
+    /// after `rewrite` hooks and before routing, so redirects also apply to
+    /// paths a `strict_slash` router wouldn't match. `/` and the query
+    /// string are left alone.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server, TrailingSlashMode};
+    ///
+    /// let mut app = Server::new();
+    /// // `/users/?page=2` is redirected to `/users?page=2`
+    /// app.trailing_slash(TrailingSlashMode::RedirectToNoSlash);
+    /// app.get("/users", |_req| async { Response::text("users") });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn trailing_slash(&mut self, mode: TrailingSlashMode) -> &mut Self {
+        self.trailing_slash = Some(mode);
+        self
+    }
+
+    /// Adds a middleware that only runs for request paths matching `predicate`
+    ///
+    /// # Arguments
+    /// * `predicate` - Returns true for paths the middleware should run on
+    /// * `middleware` - The middleware to add
+    ///
+    /// # Example
+    /// ```rust
+    /// use axeon::Server;
+    /// use axeon::middleware::{SecurityConfig, SecurityHeaders};
+    ///
+    /// let mut app = Server::new();
+    /// app.static_dir("public");
+    /// app.middleware_if(
+    ///     |path| !path.starts_with("/assets/"),
+    ///     SecurityHeaders::new(SecurityConfig::default()),
+    /// );
+    /// ```
+    pub fn middleware_if<P>(&mut self, predicate: P, middleware: impl Middleware + 'static)
+    where
+        P: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.router.middleware_if(predicate, middleware);
+    }
+
+    /// Adds a middleware that only runs for requests using one of `methods`
+    ///
+    /// # Arguments
+    /// * `methods` - The request methods the middleware should run on
+    /// * `middleware` - The middleware to add
+    pub fn middleware_for(&mut self, methods: &[Method], middleware: impl Middleware + 'static) {
+        self.router.middleware_for(methods, middleware);
+    }
+
     /// Mounts a router at a specific path
     ///
     /// # Arguments
@@ -284,92 +726,546 @@ impl Server {
         self.router.mount(path, router);
     }
 
+    /// Adds a router that is tried at request time, after the app's own
+    /// routes and any routers added before it. The first router with a
+    /// matching route handles the request.
+    ///
+    /// Unlike `mount`, the router is kept separate rather than merged into
+    /// the app's route table. App-level middleware registered so far is
+    /// applied to its routes.
+    ///
+    /// # Arguments
+    /// * `router` - The router to add to the chain
+    ///
+    /// # Example
+    /// ```rust
+    /// use axeon::{Response, Router, Server};
+    ///
+    /// let mut users = Router::new();
+    /// users.get("/users", |_req| async { Response::text("users") });
+    ///
+    /// let mut posts = Router::new();
+    /// posts.get("/posts", |_req| async { Response::text("posts") });
+    ///
+    /// let mut app = Server::new();
+    /// app.use_router(users);
+    /// app.use_router(posts);
+    /// app.fallback(|_req| async { Response::text("nothing here") });
+    /// ```
+    pub fn use_router(&mut self, router: Router) {
+        self.routers.push(router.with_middlewares(&self.router.middlewares));
+    }
+
+    /// Registers routes whose setup can fail, e.g. because they load a
+    /// template or read configuration. An error doesn't stop the other
+    /// setups from running; all of them are collected and reported by
+    /// `build`, and `listen` refuses to start while any are present.
+    ///
+    /// # Example
+    /// ```rust
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.setup(|app| {
+    ///     let page = std::fs::read_to_string("templates/missing.html")?;
+    ///     app.get("/", move |_req| {
+    ///         let page = page.clone();
+    ///         async move { Response::html(page) }
+    ///     });
+    ///     Ok::<_, std::io::Error>(())
+    /// });
+    /// app.setup(|app| {
+    ///     app.get("/health", |_req| async { Response::text("ok") });
+    ///     Ok::<_, std::io::Error>(())
+    /// });
+    ///
+    /// let err = app.build().unwrap_err();
+    /// assert_eq!(err.errors.len(), 1);
+    /// assert!(err.to_string().starts_with("1 route setup error(s): "));
+    /// assert!(app.listen("127.0.0.1:3000").is_err());
+    /// ```
+    pub fn setup<F, E>(&mut self, setup: F) -> &mut Self
+    where
+        F: FnOnce(&mut Server) -> Result<(), E>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        if let Err(err) = setup(self) {
+            self.setup_errors.push(Arc::from(err.into()));
+        }
+        self
+    }
+
+    /// Checks that every `setup` succeeded, returning all of their errors
+    /// otherwise
+    pub fn build(&self) -> Result<(), SetupError> {
+        if self.setup_errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SetupError {
+                errors: self.setup_errors.clone(),
+            })
+        }
+    }
+
+    /// Sets the handler for requests that match no route or static file
+    ///
+    /// # Arguments
+    /// * `handler` - The async handler function
+    pub fn fallback<F, R>(&mut self, handler: F)
+    where
+        F: Fn(Request) -> R + Send + Clone + Sync + 'static,
+        R: IntoResponse + 'static,
+    {
+        self.fallback = Some(Box::new(handler));
+    }
+
     /// Configure TLS for HTTPS support
+    #[cfg(feature = "tls")]
     pub fn with_tls<P: AsRef<Path>>(&mut self, cert_file: P, key_file: P) -> &mut Self {
         self.tls_config = Some(Arc::new(TlsConfig::new(cert_file, key_file)));
         self
     }
 
-    /// Starts the HTTP server
+    /// Requires HTTPS clients to present a certificate signed by a CA in
+    /// `ca_file` (mutual TLS). The verified certificate is available to
+    /// handlers as `req.client_cert`. Call after `with_tls`; otherwise it is
+    /// reported as a setup error by `build` and `listen`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use axeon::{Response, Server, ServerError};
+    ///
+    /// let mut app = Server::new();
+    /// app.with_tls("cert.pem", "key.pem").with_client_auth("clients-ca.pem");
+    /// app.get("/whoami", |req| async move {
+    ///     match req.client_cert.and_then(|cert| cert.common_name) {
+    ///         Some(name) => Response::text(name),
+    ///         None => Err(ServerError::Forbidden("No client certificate".to_string())),
+    ///     }
+    /// });
+    /// ```
+    #[cfg(feature = "tls")]
+    pub fn with_client_auth<P: AsRef<Path>>(&mut self, ca_file: P) -> &mut Self {
+        let Some(tls_config) = &self.tls_config else {
+            let err: Box<dyn std::error::Error + Send + Sync> = "with_client_auth requires with_tls to be called first".into();
+            self.setup_errors.push(Arc::from(err));
+            return self;
+        };
+        self.tls_config = Some(Arc::new(TlsConfig::clone(tls_config).with_client_auth(ca_file)));
+        self
+    }
+
+    /// Applies every setting that is set in `settings`, as if the matching
+    /// setters were called. See `ServerSettings` for loading them from the
+    /// environment or a file. Call before registering routes, since
+    /// `strict_slash` can't be changed once there are routes.
+    pub fn configure(&mut self, settings: ServerSettings) -> &mut Self {
+        if let Some(max_connections) = settings.max_connections {
+            self.max_connections(max_connections);
+        }
+        if let Some(secs) = settings.keep_alive_secs {
+            self.keep_alive(Duration::from_secs(secs));
+        }
+        if let Some(secs) = settings.request_timeout_secs {
+            self.request_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = settings.drain_timeout_secs {
+            self.drain_timeout(Duration::from_secs(secs));
+        }
+        if let Some(max_body_size) = settings.max_body_size {
+            self.max_body_size(max_body_size);
+        }
+        if let Some(limit) = settings.max_in_flight {
+            self.max_in_flight(limit);
+        }
+        if let Some(reject) = settings.reject_when_busy {
+            self.reject_when_busy(reject);
+        }
+        if let Some(strict) = settings.strict_slash {
+            self.strict_slash(strict);
+        }
+        if let Some(content_type) = &settings.default_content_type {
+            self.default_content_type(content_type);
+        }
+        if let Some(dir) = &settings.static_dir {
+            self.static_dir(dir);
+        }
+        if let Some(bytes) = settings.static_stream_threshold {
+            self.static_stream_threshold(bytes);
+        }
+        #[cfg(feature = "tls")]
+        if let (Some(cert_file), Some(key_file)) = (&settings.tls_cert_file, &settings.tls_key_file) {
+            self.with_tls(cert_file, key_file);
+            if let Some(ca_file) = &settings.tls_client_ca_file {
+                self.with_client_auth(ca_file);
+            }
+        }
+        self
+    }
+
+    /// Starts the HTTP server. Fails with a `SetupError` without binding
+    /// `addr` if any `setup` failed.
     ///
     /// # Arguments
     /// * `addr` - Address to listen on (e.g. "127.0.0.1:3000")
     pub fn listen(self, addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.listen_with_shutdown(addr, std::future::pending())
+    }
+
+    /// Starts the HTTP server and stops it when `shutdown` resolves
+    ///
+    /// Once `shutdown` resolves no new connections are accepted and idle
+    /// keep-alive connections are closed. In-flight requests get
+    /// `drain_timeout` to finish; any still running after that are cancelled
+    /// and reported to the connection error callback. Returns `Ok(())` once
+    /// draining is done.
+    ///
+    /// # Arguments
+    /// * `addr` - Address to listen on (e.g. "127.0.0.1:3000")
+    /// * `shutdown` - Future that resolves when the server should stop
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    /// use std::time::Duration;
+    ///
+    /// let mut app = Server::new();
+    /// app.drain_timeout(Duration::from_secs(5));
+    /// app.get("/", |_req| async { Response::text("Hello") });
+    /// app.listen_with_shutdown("127.0.0.1:3000", async {
+    ///     tokio::signal::ctrl_c().await.ok();
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn listen_with_shutdown<F>(self, addr: &str, shutdown: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: Future<Output = ()>,
+    {
+        self.build()?;
+        let listener = std::net::TcpListener::bind(addr)?;
+        self.serve_with_shutdown(listener, shutdown)
+    }
+
+    /// Serves connections from an already bound `listener`. Binding first
+    /// lets callers listen on port 0 and read the chosen address from
+    /// `listener.local_addr()`; clients connecting before the server
+    /// starts wait in the listen backlog.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    /// use std::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// println!("Listening on {}", listener.local_addr().unwrap());
+    /// let mut app = Server::new();
+    /// app.get("/", |_req| async { Response::text("Hello") });
+    /// app.serve(listener).unwrap();
+    /// ```
+    pub fn serve(self, listener: std::net::TcpListener) -> Result<(), Box<dyn std::error::Error>> {
+        self.serve_with_shutdown(listener, std::future::pending())
+    }
+
+    /// Like `serve`, stopping once `shutdown` resolves as described for
+    /// `listen_with_shutdown`
+    pub fn serve_with_shutdown<F>(self, listener: std::net::TcpListener, shutdown: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: Future<Output = ()>,
+    {
+        self.build()?;
+        listener.set_nonblocking(true)?;
+        // Shared by every connection rather than copied for each
+        let server = Arc::new(self);
         let runtime = Runtime::new()?;
         runtime.block_on(async {
-            let listener = TcpListener::bind(addr).await?;
-            let connection_counter = Arc::new(AtomicUsize::new(0));
-
-            println!("Server running on {}", if self.tls_config.is_some() {
-                format!("https://{}", addr)
-            } else {
-                format!("http://{}", addr)
-            });
+            let listener = TcpListener::from_std(listener)?;
+            let addr = listener.local_addr()?;
+            let connection_limit = Arc::new(Semaphore::new(server.max_connections));
 
-            let tls_acceptor = if let Some(tls_config) = &self.tls_config {
-                let certs = tls_config.load_certs()?;
-                let key = tls_config.load_key()?;
-                let config = ServerConfig::builder()
-                    .with_no_client_auth()
-                    .with_single_cert(certs, key)?;
-                Some(TlsAcceptor::from(Arc::new(config)))
-            } else {
-                None
+            #[cfg(feature = "tls")]
+            let tls_acceptor = match &server.tls_config {
+                Some(tls_config) => Some(TlsAcceptor::from(Arc::new(tls_config.server_config()?))),
+                None => None,
             };
+            #[cfg(not(feature = "tls"))]
+            let tls_acceptor: Option<()> = None;
+
+            println!(
+                "Server running on {}://{}",
+                if tls_acceptor.is_some() { "https" } else { "http" },
+                addr
+            );
+
+            let mut connections = JoinSet::new();
+            let (stopping, stop_signal) = watch::channel(false);
+            tokio::pin!(shutdown);
 
             loop {
-                let counter = Arc::clone(&connection_counter);
-                if counter.load(Ordering::Relaxed) >= self.max_connections {
-                    eprintln!("Max connections reached");
-                    continue;
-                }
+                // Wait for a free slot before accepting; the permit is held
+                // until the connection task ends
+                let permit = tokio::select! {
+                    _ = &mut shutdown => break,
+                    permit = Arc::clone(&connection_limit).acquire_owned() => permit?,
+                };
+
+                let accepted = tokio::select! {
+                    _ = &mut shutdown => break,
+                    accepted = listener.accept() => accepted,
+                };
 
-                match listener.accept().await {
-                    Ok((stream, _)) => {
-                        counter.fetch_add(1, Ordering::Relaxed);
-                        let app = self.clone();
-                        let counter = Arc::clone(&counter);
+                match accepted {
+                    Ok((stream, remote_addr)) => {
+                        let slot = ConnectionSlot::new(permit, Arc::clone(&server.active_connections));
+                        let app = Arc::clone(&server);
+                        let stop_signal = stop_signal.clone();
+                        #[cfg(feature = "tls")]
                         let acceptor = tls_acceptor.clone();
 
-                        tokio::spawn(async move {
+                        connections.spawn(async move {
+                            #[cfg(feature = "tls")]
                             let result = if let Some(acceptor) = acceptor {
                                 match acceptor.accept(stream).await {
-                                    Ok(tls_stream) => app.handle_connection(tls_stream).await,
-                                    Err(e) => {
-                                        eprintln!("TLS handshake failed: {}", e);
-                                        Ok(())
+                                    Ok(tls_stream) => {
+                                        let client_cert = tls_stream
+                                            .get_ref()
+                                            .1
+                                            .peer_certificates()
+                                            .and_then(|certs| certs.first())
+                                            .and_then(|cert| ClientCertInfo::from_der(cert));
+                                        app.handle_connection(tls_stream, client_cert, Some(remote_addr), stop_signal).await
                                     }
+                                    Err(e) => Err(e),
                                 }
                             } else {
-                                app.handle_connection(stream).await
+                                app.handle_connection(stream, None, Some(remote_addr), stop_signal).await
                             };
+                            #[cfg(not(feature = "tls"))]
+                            let result = app.handle_connection(stream, None, Some(remote_addr), stop_signal).await;
 
                             if let Err(e) = result {
-                                eprintln!("Connection error: {}", e);
+                                (app.on_connection_error)(&e);
                             }
-                            counter.fetch_sub(1, Ordering::Relaxed);
+                            drop(slot);
                         });
                     }
-                    Err(e) => eprintln!("Connection failed: {}", e),
+                    Err(e) => (server.on_accept_error)(&e),
+                }
+
+                // Reap finished connections so the set doesn't grow unbounded
+                while connections.try_join_next().is_some() {}
+            }
+
+            // Stop accepting and close idle connections, then give in-flight
+            // requests until the deadline
+            drop(listener);
+            stopping.send_replace(true);
+            let drain = async { while connections.join_next().await.is_some() {} };
+            if timeout(server.drain_timeout, drain).await.is_err() {
+                let cancelled = connections.len();
+                connections.shutdown().await;
+                let err = Error::new(ErrorKind::TimedOut, "Connection cancelled at shutdown drain deadline");
+                for _ in 0..cancelled {
+                    (server.on_connection_error)(&err);
                 }
             }
+            Ok(())
         })
     }
 
-    async fn handle_connection<S>(&self, mut stream: S) -> Result<(), Error>
+    async fn handle_connection<S>(
+        self: &Arc<Self>,
+        stream: S,
+        client_cert: Option<ClientCertInfo>,
+        remote_addr: Option<SocketAddr>,
+        mut stop_signal: watch::Receiver<bool>,
+    ) -> Result<(), Error>
     where
         S: AsyncRead + AsyncWrite + Unpin,
     {
-        let mut buf_reader = BufReader::new(&mut stream);
-        let mut request_line = String::new();
-        buf_reader.read_line(&mut request_line).await?;
+        // The reader is kept across requests so pipelined bytes aren't lost
+        let mut buf_reader = BufReader::new(stream);
 
-        if request_line.is_empty() {
-            return Ok(());
+        loop {
+            let mut request_line = String::new();
+
+            // Close connections that stay idle between requests, or that are
+            // idle when the server shuts down
+            let read = tokio::select! {
+                read = timeout(self.keep_alive, buf_reader.read_line(&mut request_line)) => read,
+                _ = stop_signal.wait_for(|stopping| *stopping) => return Ok(()),
+            };
+            match read {
+                Ok(result) => {
+                    result?;
+                }
+                Err(_) => return Ok(()),
+            }
+
+            if request_line.is_empty() {
+                return Ok(());
+            }
+
+            // The request timeout covers reading headers and body plus the handler,
+            // unless the matched route sets its own timeout for the handler
+            let deadline = Instant::now() + self.request_timeout;
+            // Framing follows the method the client sent, whatever rewrites do
+            let method = Method::from_string(request_line.split_whitespace().next().unwrap_or(""));
+            let read = timeout_at(deadline, self.read_request(&request_line, &mut buf_reader, client_cert.clone())).await;
+            let (mut response, keep_alive) = match read {
+                Ok(Ok(mut request)) => {
+                    request.deadline = Some(deadline.into_std());
+                    request.remote_addr = remote_addr;
+                    let keep_alive = Self::wants_keep_alive(&request_line, &request);
+                    // HTTP/1.0 clients don't understand interim responses
+                    let response = if request_line.split_whitespace().nth(2) == Some("HTTP/1.0") {
+                        self.respond(request, deadline).await
+                    } else {
+                        self.respond_with_early_hints(request, deadline, buf_reader.get_mut()).await?
+                    };
+                    (response, keep_alive)
+                }
+                // Malformed requests get a 400 and the connection is closed
+                Ok(Err(err)) if err.kind() == ErrorKind::InvalidData => {
+                    (self.handle_error(ServerError::BadRequest(err.to_string())), false)
+                }
+                Ok(Err(err)) => match Self::server_error(err) {
+                    Ok(server_error) => (self.handle_error(server_error), false),
+                    Err(err) => return Err(err),
+                },
+                // The body may be partially read, so the stream can't be reused
+                Err(_) => (self.handle_error(ServerError::Timeout), false),
+            };
+
+            let keep_alive = keep_alive
+                && !response.headers.iter().any(|(name, value)| {
+                    name.eq_ignore_ascii_case("connection") && value.eq_ignore_ascii_case("close")
+                });
+            response.set_header("Connection", if keep_alive { "keep-alive" } else { "close" });
+
+            Self::write_response(buf_reader.get_mut(), response, method).await?;
+            if !keep_alive {
+                return Ok(());
+            }
+        }
+    }
+
+    // Runs `respond` while writing any 103 Early Hints the handler or its
+    // middleware emit. Hints still queued when the response is ready are
+    // written first, so they always precede it.
+    async fn respond_with_early_hints<W>(self: &Arc<Self>, mut request: Request, deadline: Instant, stream: &mut W) -> Result<Response, Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let (hints, mut queued) = mpsc::unbounded_channel();
+        request.early_hints = Some(hints);
+        let respond = self.respond(request, deadline);
+        tokio::pin!(respond);
+
+        let response = loop {
+            tokio::select! {
+                biased;
+                Some(links) = queued.recv() => Self::write_early_hints(stream, &links).await?,
+                response = &mut respond => break response,
+            }
+        };
+        while let Ok(links) = queued.try_recv() {
+            Self::write_early_hints(stream, &links).await?;
+        }
+        Ok(response)
+    }
+
+    async fn write_early_hints<W>(stream: &mut W, links: &[String]) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut interim = String::from("HTTP/1.1 103 Early Hints\r\n");
+        for link in links {
+            interim += &format!("Link: {}\r\n", link);
+        }
+        interim += "\r\n";
+        stream.write_all(interim.as_bytes()).await?;
+        stream.flush().await
+    }
+
+    /// HTTP/1.1 connections are persistent unless either side sends
+    /// `Connection: close`; HTTP/1.0 ones only with `Connection: keep-alive`.
+    fn wants_keep_alive(request_line: &str, request: &Request) -> bool {
+        let connection = request.headers.get("connection").map(|value| value.to_ascii_lowercase());
+        let tokens = connection.as_deref().unwrap_or("");
+        let has = |token: &str| tokens.split(',').any(|t| t.trim() == token);
+        if request_line.split_whitespace().nth(2) == Some("HTTP/1.0") {
+            has("keep-alive")
+        } else {
+            !has("close")
+        }
+    }
+
+    // Responses to HEAD keep the headers a GET would get, Content-Length
+    // included, but never write body bytes or chunks
+    async fn write_response<W>(stream: &mut W, mut response: Response, method: Method) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut response_line = format!("HTTP/1.1 {}\r\n", response.status);
+        // Message framing is always derived from the final body below, so any
+        // Content-Length or Transfer-Encoding set by handlers or middleware is
+        // dropped here
+        response
+            .headers
+            .iter()
+            .filter(|(name, _)| {
+                !name.eq_ignore_ascii_case("content-length")
+                    && !name.eq_ignore_ascii_case("transfer-encoding")
+            })
+            .for_each(|(name, value)| {
+                response_line += &format!("{}: {}\r\n", name, value);
+            });
+
+        // 1xx, 204 and 304 responses never carry a body or Content-Length
+        let body_stream = response.stream.take();
+        let no_body = matches!(response.status, 100..=199 | 204 | 304);
+        if no_body {
+            response_line += "\r\n";
+        } else if body_stream.is_some() {
+            response_line += "Transfer-Encoding: chunked\r\n\r\n";
+        } else {
+            response_line += &format!("Content-Length: {}\r\n\r\n", response.body.len());
+        }
+        stream.write_all(response_line.as_bytes()).await?;
+        if no_body || method == Method::HEAD {
+            return stream.flush().await;
+        }
+
+        match body_stream {
+            Some(BodyStream(mut chunks)) => {
+                while let Some(chunk) = chunks.next().await {
+                    let chunk = chunk?;
+                    if chunk.is_empty() {
+                        continue;
+                    }
+                    stream.write_all(format!("{:x}\r\n", chunk.len()).as_bytes()).await?;
+                    stream.write_all(&chunk).await?;
+                    stream.write_all(b"\r\n").await?;
+                    // Push each chunk out right away, e.g. for event streams
+                    stream.flush().await?;
+                }
+                stream.write_all(b"0\r\n\r\n").await?;
+            }
+            None => stream.write_all(&response.body).await?,
         }
+        stream.flush().await
+    }
 
+    async fn read_request<S>(
+        &self,
+        request_line: &str,
+        buf_reader: &mut BufReader<S>,
+        client_cert: Option<ClientCertInfo>,
+    ) -> Result<Request, Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
         // Parse the request line
-        let mut parts = request_line.trim().split_whitespace();
+        let mut parts = request_line.split_whitespace();
         let method = parts
             .next()
             .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid request line"))?
@@ -378,19 +1274,11 @@ impl Server {
         let full_path = parts
             .next()
             .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid request line"))?;
-
-        // Split path and query
-        let mut path_parts = full_path.split('?');
-        let path = path_parts.next().unwrap_or("/").to_string();
-        let path = path.trim_end_matches('/').to_string();
-        let path = if path.is_empty() { "/".to_string() } else { path };
-        let query = path_parts
-            .next()
-            .map(|query| Self::parse_query(query))
-            .unwrap_or_default();
+        let version = parts.next();
 
         // Parse headers efficiently
         let mut headers = HashMap::new();
+        let mut header_lines = Vec::new();
         loop {
             let mut line = String::new();
             buf_reader.read_line(&mut line).await?;
@@ -400,39 +1288,135 @@ impl Server {
             }
 
             if let Some((key, value)) = line.trim().split_once(':') {
-                headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+                let (key, value) = (key.trim().to_lowercase(), value.trim().to_string());
+                header_lines.push((key.clone(), value.clone()));
+                headers.insert(key, value);
             }
         }
 
-        // Read body if Content-Length is present
+        // A request with both headers is ambiguous and a request smuggling vector
+        if headers.contains_key("content-length") && headers.contains_key("transfer-encoding") {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Both Content-Length and Transfer-Encoding present",
+            ));
+        }
+
+        // An unparsable or conflicting length would leave the rest of the
+        // body to be read as the next request
+        let content_length = Self::declared_content_length(&header_lines)?;
+        if let Some(length) = content_length {
+            headers.insert("content-length".to_string(), length.to_string());
+        }
+
+        // The client waits for the go-ahead before sending the body
+        let expects_continue = headers
+            .get("expect")
+            .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"));
+
+        // Read body if Content-Length or chunked Transfer-Encoding is present
         let mut body = Vec::new();
-        let mut content_type = "none".to_owned();
-        if headers.contains_key("content-type") {
-            content_type = headers["content-type"].clone();
-        }
-        if let Some(content_length) = headers.get("content-length") {
-            if let Ok(length) = content_length.parse::<usize>() {
-                body.reserve(length);
-                let mut take = buf_reader.take(length as u64);
-                take.read_to_end(&mut body).await?;
+        if let Some(transfer_encoding) = headers.get("transfer-encoding") {
+            // Only a plain `chunked` coding is supported; without chunked as
+            // the final coding the body length can't be determined
+            let codings: Vec<String> = transfer_encoding
+                .split(',')
+                .map(|coding| coding.trim().to_ascii_lowercase())
+                .collect();
+            if codings.last().map(String::as_str) != Some("chunked") {
+                return Err(Error::new(ErrorKind::InvalidData, "Request body length can't be determined"));
+            }
+            if codings.len() > 1 {
+                return Err(Error::other(ServerError::NotImplemented));
+            }
+
+            if expects_continue {
+                let stream = buf_reader.get_mut();
+                stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+                stream.flush().await?;
             }
+            body = Self::read_chunked_body(buf_reader, self.max_body_size).await?;
+            // The body handlers see is no longer chunked
+            headers.remove("transfer-encoding");
+            headers.insert("content-length".to_string(), body.len().to_string());
+        } else if let Some(length) = content_length {
+            // Refuse oversized bodies before allocating for them
+            if length > self.max_body_size {
+                return Err(Error::other(ServerError::PayloadTooLarge));
+            }
+
+            if expects_continue && length > 0 {
+                let stream = buf_reader.get_mut();
+                stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+                stream.flush().await?;
+            }
+
+            body.reserve(length);
+            let mut take = buf_reader.take(length as u64);
+            take.read_to_end(&mut body).await?;
         }
 
-        let request = Request {
-            method: Method::from_string(&method),
-            path,
-            query,
-            headers,
-            body: Body {
-                content_type: content_type.to_string(),
-                data: body,
-            },
-            params: HashMap::new(),
-            data: HashMap::new(),
-            plugins: self.plugins.clone(),
+        #[cfg(feature = "compression")]
+        if let Some(encoding) = headers.get("content-encoding").map(|e| e.trim().to_ascii_lowercase()) {
+            if let Some(decoded) = Self::decode_body(&encoding, &body, self.max_body_size)? {
+                body = decoded;
+                // The body handlers see is no longer encoded
+                headers.remove("content-encoding");
+            }
+        }
+        let content_type = match headers.get("content-type") {
+            Some(content_type) => content_type.clone(),
+            None => self.fallback_content_type(&body),
+        };
+
+        let mut request = Request::new(Method::from_string(&method), full_path);
+        if let Some(version) = version {
+            request.version = version.to_string();
+        }
+        request.headers = headers;
+        request.header_lines = header_lines;
+        request.body = Body {
+            content_type,
+            data: body,
         };
+        request.plugins = self.plugins.clone();
+        request.client_cert = client_cert;
+        Ok(request)
+    }
+
+    // The body length from every Content-Length line. Repeated lines or
+    // comma-separated values are accepted only when they all agree.
+    fn declared_content_length(header_lines: &[(String, String)]) -> Result<Option<usize>, Error> {
+        let mut declared = None;
+        let values = header_lines
+            .iter()
+            .filter(|(key, _)| key == "content-length")
+            .flat_map(|(_, value)| value.split(','));
+        for value in values {
+            let value = value.trim();
+            let length = match value.parse::<usize>() {
+                Ok(length) if value.bytes().all(|b| b.is_ascii_digit()) => length,
+                _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid Content-Length")),
+            };
+            if declared.is_some_and(|declared| declared != length) {
+                return Err(Error::new(ErrorKind::InvalidData, "Conflicting Content-Length values"));
+            }
+            declared = Some(length);
+        }
+        Ok(declared)
+    }
 
-        let response = AssertUnwindSafe(self.handle(request)).catch_unwind().await;
+    async fn respond(self: &Arc<Self>, request: Request, deadline: Instant) -> Response {
+        let method = request.method;
+        let if_none_match = request.headers.get("if-none-match").cloned();
+        let if_modified_since = request.headers.get("if-modified-since").cloned();
+        let timing = request.server_timing();
+
+        // The in-flight slot is held until the handler has produced a response
+        let response = match self.acquire_in_flight(deadline).await {
+            Ok(_slot) => AssertUnwindSafe(self.handle(request, deadline)).catch_unwind().await,
+            Err(err) => Ok(Err(err)),
+        };
         let response = match response {
             Ok(response) => response,
             Err(err) => {
@@ -443,27 +1427,77 @@ impl Server {
                 } else {
                     "Unknown panic".to_string()
                 };
+                (self.on_panic)(&panic_msg);
+                if !self.expose_panic_messages {
+                    // The detail only goes to on_panic; clients get a plain 500
+                    let mut response = Response::new(500);
+                    response.header("Content-Type", "text/plain").body("Internal Server Error");
+                    Self::add_server_timing(&mut response, &timing);
+                    return response;
+                }
                 Err(ServerError::PanicError(panic_msg))
             },
         };
-        let response = match response {
+        let mut response = match response {
             Ok(response) => response,
-            Err(err) => self.handle_error(err),
+            Err(err) => {
+                let mut response = self.handle_error(err);
+                Self::add_server_timing(&mut response, &timing);
+                return response;
+            }
         };
-        let mut response_line = format!("HTTP/1.1 {}\r\n", response.status);
-        response.headers.iter().for_each(|(name, value)| {
-            response_line += &format!("{}: {}\r\n", name, value);
-        });
 
-        let contents = &response.body;
-        let length = contents.len();
-        response_line += &format!("Content-Length: {}\r\n\r\n{}", length, contents);
-        stream.write_all(response_line.as_bytes()).await?;
-        Ok(())
+        // Conditional GET: downgrade to 304 when the client's copy is current
+        if matches!(method, Method::GET | Method::HEAD)
+            && response.status == 200
+            && Self::response_not_modified(&response, if_none_match.as_deref(), if_modified_since.as_deref())
+        {
+            response.status = 304;
+            response.body.clear();
+            response.stream = None;
+        }
+        Self::add_server_timing(&mut response, &timing);
+        response
+    }
+
+    // Takes a `max_in_flight` slot, waiting until `deadline` unless
+    // `reject_when_busy` is set. Without a limit there is nothing to take.
+    async fn acquire_in_flight(&self, deadline: Instant) -> Result<Option<OwnedSemaphorePermit>, ServerError> {
+        let Some(limit) = &self.in_flight_limit else {
+            return Ok(None);
+        };
+        if self.reject_when_busy {
+            return Arc::clone(limit)
+                .try_acquire_owned()
+                .map(Some)
+                .map_err(|_| ServerError::ServiceUnavailable);
+        }
+        match timeout_at(deadline, Arc::clone(limit).acquire_owned()).await {
+            Ok(permit) => permit
+                .map(Some)
+                .map_err(|err| ServerError::InternalError(err.to_string())),
+            Err(_) => Err(ServerError::Timeout),
+        }
+    }
+
+    fn add_server_timing(response: &mut Response, timing: &ServerTiming) {
+        if let Some(value) = timing.header_value() {
+            response.append_header("Server-Timing", value);
+        }
     }
 
     /// Sets the directory for serving static files
     ///
+    /// A `Range: bytes=start-end`, `bytes=start-` or `bytes=-suffix`
+    /// request gets a 206 with only those bytes, streamed from the file with
+    /// chunked encoding. A range starting past the end of the file gets a
+    /// 416; malformed or multi-part ranges are ignored and the whole file is
+    /// sent.
+    ///
+    /// A client revalidating its cached copy with `If-None-Match` or
+    /// `If-Modified-Since` gets a 304 without a body while the file is
+    /// unchanged.
+    ///
     /// # Arguments
     /// * `dir` - Path to the static files directory
     ///
@@ -480,186 +1514,532 @@ impl Server {
         self
     }
 
-    async fn handle(&self, mut req: Request) -> HttpResponse {
-        let path = req.path.clone();
-        let method = req.method.clone();
-        if let Some(routes) = self.router.routes.get(&path) {
+    /// Sets the size in bytes above which static files are streamed from
+    /// disk in chunks instead of being read into memory first (default
+    /// 1 MiB). Streamed files are sent with chunked transfer encoding.
+    ///
+    /// # Example
+    /// ```rust
+    /// use axeon::Server;
+    ///
+    /// let mut app = Server::new();
+    /// app.static_dir("public").static_stream_threshold(4 * 1024 * 1024);
+    /// ```
+    pub fn static_stream_threshold(&mut self, bytes: u64) -> &mut Self {
+        self.static_stream_threshold = bytes;
+        self
+    }
+
+    async fn handle(self: &Arc<Self>, req: Request, deadline: Instant) -> HttpResponse {
+        let mut req = req;
+        for rewrite in &self.rewrites {
+            rewrite(&mut req);
+        }
+
+        if self.global_middlewares.middlewares.is_empty() {
+            return self.dispatch(req, deadline).await;
+        }
+        let app = Arc::clone(self);
+        let next = Next::new(move |req: Request| {
+            let app = Arc::clone(&app);
+            async move { app.dispatch(req, deadline).await }
+        });
+        Self::with_deadline(deadline, self.global_middlewares.call(req, next)).await
+    }
+
+    // Routes a request to a handler, a static file or the fallback
+    async fn dispatch(&self, req: Request, deadline: Instant) -> HttpResponse {
+        let raw_path = req.raw_path().to_string();
+        let toggled_path = if raw_path.ends_with('/') {
+            format!("/{}", raw_path.trim_matches('/'))
+        } else {
+            format!("{}/", raw_path)
+        };
+        match self.trailing_slash {
+            Some(TrailingSlashMode::RedirectToNoSlash) if raw_path != "/" && raw_path.ends_with('/') => {
+                return Self::redirect_keeping_query(&req, &toggled_path);
+            }
+            Some(TrailingSlashMode::RedirectToSlash) if !raw_path.ends_with('/') => {
+                return Self::redirect_keeping_query(&req, &toggled_path);
+            }
+            _ => {}
+        }
+
+        let mut req = match self.route_all(req, deadline).await {
+            Ok(response) => return response,
+            Err(unmatched) => unmatched,
+        };
+        // Transparent mode tries the other form before giving up
+        if self.trailing_slash == Some(TrailingSlashMode::Transparent) && raw_path != "/" {
+            req.raw_path = toggled_path;
+            req = match self.route_all(req, deadline).await {
+                Ok(response) => return response,
+                Err(unmatched) => unmatched,
+            };
+            req.raw_path = raw_path;
+        }
+
+        // The path is routed, just not for this method
+        let allowed = self.allowed_methods(&req);
+        if !allowed.is_empty() {
+            let allowed: Vec<String> = allowed.iter().map(|method| format!("{:?}", method)).collect();
+            let allowed: Vec<&str> = allowed.iter().map(String::as_str).collect();
+            return Response::method_not_allowed(&allowed);
+        }
+
+        // Static files and the fallback run through the app-level middleware
+        if let Some(file_path) = self
+            .static_dir
+            .as_deref()
+            .and_then(|dir| Self::resolve_static_file(dir, &req.path))
+        {
+            let stream_threshold = self.static_stream_threshold;
+            let next = Next::new(move |req: Request| {
+                let response = Self::serve_file(&file_path, &req, stream_threshold).ok_or(ServerError::NotFound);
+                async move { response }
+            });
+            return Self::with_deadline(deadline, self.router.middlewares.call(req, next)).await;
+        }
+        if let Some(fallback) = &self.fallback {
+            let next = Next::new_handler(fallback.clone());
+            return Self::with_deadline(deadline, self.router.middlewares.call(req, next)).await;
+        }
+        Err(ServerError::NotFound)
+    }
+
+    // Reads a chunked body up to its zero-size last chunk, discarding chunk
+    // extensions and trailer fields. Bodies over `limit` fail with
+    // PayloadTooLarge before the oversized chunk is read; size lines over
+    // 4 KiB or more than 32 trailer fields are rejected as malformed.
+    async fn read_chunked_body<S>(buf_reader: &mut BufReader<S>, limit: usize) -> Result<Vec<u8>, Error>
+    where
+        S: AsyncRead + Unpin,
+    {
+        const MAX_TRAILERS: usize = 32;
+        let malformed = || Error::new(ErrorKind::InvalidData, "Malformed chunked request body");
+        let mut body = Vec::new();
+        loop {
+            let line = Self::read_chunk_line(buf_reader).await?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16).map_err(|_| malformed())?;
+
+            if size == 0 {
+                for _ in 0..=MAX_TRAILERS {
+                    if Self::read_chunk_line(buf_reader).await?.trim().is_empty() {
+                        return Ok(body);
+                    }
+                }
+                return Err(malformed());
+            }
+
+            if body.len().saturating_add(size) > limit {
+                return Err(Error::other(ServerError::PayloadTooLarge));
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            buf_reader.read_exact(&mut body[start..]).await?;
+
+            if !Self::read_chunk_line(buf_reader).await?.trim().is_empty() {
+                return Err(malformed());
+            }
+        }
+    }
+
+    // Reads one line of chunked framing. A line missing its newline was cut
+    // off by the 4 KiB cap or by the connection closing.
+    async fn read_chunk_line<S>(buf_reader: &mut BufReader<S>) -> Result<String, Error>
+    where
+        S: AsyncRead + Unpin,
+    {
+        const MAX_LINE: u64 = 4096;
+        let mut line = String::new();
+        buf_reader.take(MAX_LINE + 1).read_line(&mut line).await?;
+        if line.ends_with('\n') {
+            Ok(line)
+        } else {
+            Err(Error::new(ErrorKind::InvalidData, "Malformed chunked request body"))
+        }
+    }
+
+    // Inflates a gzip or deflate request body, or returns None for other
+    // encodings. Bodies inflating past `limit` fail with PayloadTooLarge.
+    #[cfg(feature = "compression")]
+    fn decode_body(encoding: &str, body: &[u8], limit: usize) -> Result<Option<Vec<u8>>, Error> {
+        use std::io::Read;
+
+        let decoder: Box<dyn Read + '_> = match encoding {
+            "gzip" | "x-gzip" => Box::new(flate2::read::GzDecoder::new(body)),
+            "deflate" => Box::new(flate2::read::ZlibDecoder::new(body)),
+            _ => return Ok(None),
+        };
+        let mut decoded = Vec::new();
+        decoder
+            .take(limit as u64 + 1)
+            .read_to_end(&mut decoded)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        if decoded.len() > limit {
+            return Err(Error::other(ServerError::PayloadTooLarge));
+        }
+        Ok(Some(decoded))
+    }
+
+    // Recovers a ServerError that read_request wrapped in an io::Error
+    fn server_error(err: Error) -> Result<ServerError, Error> {
+        if !err.get_ref().is_some_and(|inner| inner.is::<ServerError>()) {
+            return Err(err);
+        }
+        let kind = err.kind();
+        let inner = err.into_inner().expect("checked above");
+        inner
+            .downcast::<ServerError>()
+            .map(|server_error| *server_error)
+            .map_err(|inner| Error::new(kind, inner))
+    }
+
+    // Methods a request to `path` can use across all routers
+    fn allowed_methods(&self, req: &Request) -> Vec<Method> {
+        let methods = std::iter::once(&self.router)
+            .chain(&self.routers)
+            .flat_map(|router| router.allowed_methods(&router.lookup_path(req)))
+            .collect();
+        Self::with_implied_methods(methods)
+    }
+
+    // Adds HEAD, served by GET routes, and OPTIONS, served for any route
+    fn with_implied_methods(mut methods: Vec<Method>) -> Vec<Method> {
+        if methods.is_empty() {
+            return methods;
+        }
+        if methods.contains(&Method::GET) {
+            methods.push(Method::HEAD);
+        }
+        methods.push(Method::OPTIONS);
+        methods.sort_by_key(|method| *method as u8);
+        methods.dedup();
+        methods
+    }
+
+    // Dispatches to a matching route in `router`, handing the request back
+    // if nothing matched so the next router can try it
+    async fn route(router: &Router, mut req: Request, deadline: Instant) -> Result<HttpResponse, Request> {
+        let path = router.lookup_path(&req);
+        let method = req.method;
+        if let Some(routes) = router.routes.get(&path) {
             if let Some(route) = routes.get(&method) {
-                return route.handle(req).await;
+                return Ok(route.handle(req, deadline).await);
             } else {
                 if method == Method::HEAD {
                     if let Some(route) = routes.get(&Method::GET) {
-                        return Self::handle_head(route.clone(), req).await;
+                        return Ok(Self::handle_head(route.clone(), req, deadline).await);
                     }
                 }
                 if method == Method::OPTIONS {
-                    if let Some(route) = routes.get(&Method::GET) {
-                        return Self::handle_options(route.clone(), req).await;
+                    // Preflight runs through the middleware of the route's own
+                    // router (e.g. its Cors), whichever method it registered
+                    if let Some(route) = Self::options_route(routes) {
+                        let allowed = Self::with_implied_methods(router.allowed_methods(&path));
+                        return Ok(Self::handle_options(route.clone(), &allowed, req, deadline).await);
                     }
                 }
             }
         }
 
-        for dynamic_path in &self.router.dynamic_routes {
-            if let Some(params) = self.match_dynamic_path(dynamic_path, &path) {
-                if let Some(routes) = self.router.routes.get(dynamic_path) {
-                    let method = req.method.clone();
-                    if let Some(route) = routes.get(&method) {
-                        req.params = params;
-                        return route.handle(req).await;
-                    } else {
-                        if method == Method::HEAD {
-                            if let Some(route) = routes.get(&Method::GET) {
-                                return Self::handle_head(route.clone(), req).await;
-                            }
+        for (dynamic_path, params) in router.dynamic_routes.matches(&path) {
+            if let Some(routes) = router.routes.get(dynamic_path) {
+                if let Some(route) = routes.get(&method) {
+                    req.params = params;
+                    return Ok(route.handle(req, deadline).await);
+                } else {
+                    if method == Method::HEAD {
+                        if let Some(route) = routes.get(&Method::GET) {
+                            req.params = params;
+                            return Ok(Self::handle_head(route.clone(), req, deadline).await);
                         }
-                        if method == Method::OPTIONS {
-                            if let Some(route) = routes.get(&Method::GET) {
-                                req.params = params;
-                                return Self::handle_options(route.clone(), req).await;
-                            }
+                    }
+                    if method == Method::OPTIONS {
+                        if let Some(route) = Self::options_route(routes) {
+                            req.params = params;
+                            let allowed = Self::with_implied_methods(router.allowed_methods(&path));
+                            return Ok(Self::handle_options(route.clone(), &allowed, req, deadline).await);
                         }
                     }
                 }
             }
         }
-        if let Some(response) = self.handle_static_file(&req.path) {
-            Ok(response)
+        Err(req)
+    }
+
+    // Tries each router in turn, handing the request back if none matched
+    async fn route_all(&self, mut req: Request, deadline: Instant) -> Result<HttpResponse, Request> {
+        for router in std::iter::once(&self.router).chain(&self.routers) {
+            match Self::route(router, req, deadline).await {
+                Ok(response) => return Ok(response),
+                Err(unmatched) => req = unmatched,
+            }
+        }
+        Err(req)
+    }
+
+    fn redirect_keeping_query(req: &Request, path: &str) -> HttpResponse {
+        if req.raw_query().is_empty() {
+            Response::permanent_redirect(path)
         } else {
-            Err(ServerError::NotFound)
+            Response::permanent_redirect(&format!("{}?{}", path, req.raw_query()))
         }
     }
 
-    async fn handle_head(route: Route, req: Request) -> HttpResponse {
+    // The route whose middleware answers an automatic OPTIONS request: the
+    // first registered method in `Method` order, so GET if there is one.
+    // Routes of one path can have different middleware, so picking by map
+    // order would make the response vary from run to run
+    fn options_route(routes: &HashMap<Method, Route>) -> Option<&Route> {
+        routes
+            .iter()
+            .min_by_key(|(method, _)| **method as u8)
+            .map(|(_, route)| route)
+    }
+
+    async fn handle_head(route: Route, req: Request, deadline: Instant) -> HttpResponse {
         let mut req = req;
         req.method = Method::GET;
-        let response = route.handle(req).await;
-        match response {
-            Ok(mut response) => {
-                response.body = "".to_string();
-                Ok(response)
-            }
-            Err(err) => Err(err),
-        }
+        // The body is kept so the GET Content-Length is sent; write_response
+        // leaves it out
+        route.handle(req, deadline).await
     }
 
-    async fn handle_options(route: Route, req: Request) -> HttpResponse {
+    // Answers with the allowed methods, through the matched route's
+    // middleware so router-specific layers such as Cors still apply
+    async fn handle_options(route: Route, allowed: &[Method], req: Request, deadline: Instant) -> HttpResponse {
+        let allow = allowed
+            .iter()
+            .map(|method| format!("{:?}", method))
+            .collect::<Vec<_>>()
+            .join(", ");
         let route = Route {
             middlewares: route.middlewares.clone(),
-            handler: Box::new(|_| async { Ok(Response::new(200)) }),
+            handler: Box::new(move |_| {
+                let allow = allow.clone();
+                async move {
+                    let mut response = Response::new(204);
+                    response.header("Allow", allow);
+                    Ok(response)
+                }
+            }),
+            timeout: route.timeout,
         };
-        route.handle(req).await
+        route.handle(req, deadline).await
+    }
+
+    async fn with_deadline<F>(deadline: Instant, response: F) -> HttpResponse
+    where
+        F: Future<Output = HttpResponse>,
+    {
+        timeout_at(deadline, response)
+            .await
+            .unwrap_or(Err(ServerError::Timeout))
     }
 
     fn handle_error(&self, error: ServerError) -> Response {
         if let Some(handler) = &self.on_error {
-            handler(error)
+            // Custom formatters still get the error's headers unless they set their own
+            let headers = error.headers();
+            let mut response = handler(error);
+            for (name, value) in headers {
+                if !response.headers.keys().any(|key| key.eq_ignore_ascii_case(&name)) {
+                    response.headers.insert(name, value);
+                }
+            }
+            response
         } else {
             Response::error(error)
         }
     }
 
-    fn handle_static_file(&self, path: &str) -> Option<Response> {
-        if let Some(static_dir) = &self.static_dir {
-            let file_path = static_dir.join(path.trim_start_matches('/'));
-            if let Ok(canonical_path) = fs::canonicalize(&file_path) {
-                if canonical_path.starts_with(fs::canonicalize(static_dir).ok()?)
-                    && canonical_path.is_file()
-                {
-                    return self.serve_file(&canonical_path);
-                }
-            }
+    fn resolve_static_file(static_dir: &Path, path: &str) -> Option<PathBuf> {
+        let file_path = static_dir.join(path.trim_start_matches('/'));
+        let canonical_path = fs::canonicalize(file_path).ok()?;
+        if canonical_path.starts_with(fs::canonicalize(static_dir).ok()?) && canonical_path.is_file() {
+            Some(canonical_path)
+        } else {
+            None
         }
-        None
-    }
-
-    fn serve_file(&self, path: &Path) -> Option<Response> {
-        if let Ok(contents) = fs::read(path) {
-            let mut response = Response::new(200);
-
-            // Set content type based on file extension
-            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                let content_type = match ext {
-                    "html" => "text/html",
-                    "css" => "text/css",
-                    "js" => "text/javascript",
-                    "png" => "image/png",
-                    "jpg" | "jpeg" => "image/jpeg",
-                    "gif" => "image/gif",
-                    "svg" => "image/svg+xml",
-                    "ico" => "image/x-icon",
-                    _ => "application/octet-stream",
-                };
-                response.header("Content-Type", content_type);
-            }
+    }
 
-            // Set cache control headers
-            response.header("Cache-Control", "public, max-age=31536000");
-
-            // Set Last-Modified
-            if let Ok(metadata) = fs::metadata(path) {
-                if let Ok(modified) = metadata.modified() {
-                    if let Ok(duration) = modified.duration_since(SystemTime::UNIX_EPOCH) {
-                        response.header(
-                            "Last-Modified",
-                            &httpdate::fmt_http_date(std::time::UNIX_EPOCH + duration),
-                        );
-                    }
+    fn serve_file(path: &Path, req: &Request, stream_threshold: u64) -> Option<Response> {
+        let metadata = fs::metadata(path).ok()?;
+        let len = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok());
+        // ETag from file size and modification time as a simple hash
+        let etag = format!("\"{}-{}\"", len, modified.map(|m| m.as_secs()).unwrap_or(0));
+
+        let mut response = if Self::is_not_modified(req, &etag, modified) {
+            Response::new(304)
+        } else {
+            let range = req
+                .get_header("range")
+                .and_then(|range| Self::parse_range(range, len));
+            match range {
+                Some(ByteRange::Unsatisfiable) => {
+                    let mut response = Response::new(416);
+                    response
+                        .header("Accept-Ranges", "bytes")
+                        .header("Content-Range", format!("bytes */{}", len));
+                    return Some(response);
+                }
+                Some(ByteRange::Satisfiable(start, end)) => {
+                    // Only the requested slice is read, in chunks
+                    let mut file = fs::File::open(path).ok()?;
+                    file.seek(SeekFrom::Start(start)).ok()?;
+                    let slice = tokio::fs::File::from_std(file).take(end - start + 1);
+                    let mut response = Response::from_reader(slice, "application/octet-stream").ok()?;
+                    response.status = 206;
+                    response.header("Content-Range", format!("bytes {}-{}/{}", start, end, len));
+                    response
+                }
+                // Large files are streamed rather than read into memory
+                None if len > stream_threshold => {
+                    let file = tokio::fs::File::from_std(fs::File::open(path).ok()?);
+                    Response::from_reader(file, "application/octet-stream").ok()?
+                }
+                None => {
+                    let mut response = Response::new(200);
+                    response.body = fs::read(path).ok()?;
+                    response
                 }
             }
+        };
 
-            // Set ETag (using file size and modification time as a simple hash)
-            if let Ok(metadata) = fs::metadata(path) {
-                let etag = format!(
-                    "\"{}-{}\"",
-                    metadata.len(),
-                    metadata
-                        .modified()
-                        .map(|m| m.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs())
-                        .unwrap_or(0)
-                );
-                response.header("ETag", &etag);
-            }
+        response.header("Accept-Ranges", "bytes");
 
-            response.body = String::from_utf8_lossy(&contents).to_string();
-            Some(response)
-        } else {
-            None
+        // Set content type based on file extension
+        if path.extension().is_some() {
+            response.header("Content-Type", content_type_for(path));
+        }
+
+        // Set cache control headers
+        response.header("Cache-Control", "public, max-age=31536000");
+
+        if let Some(modified) = modified {
+            response.header(
+                "Last-Modified",
+                httpdate::fmt_http_date(SystemTime::UNIX_EPOCH + modified),
+            );
         }
+        response.header("ETag", &etag);
+
+        Some(response)
     }
 
-    fn parse_query(query: &str) -> HashMap<String, String> {
-        query
-            .split('&')
-            .filter(|s| !s.is_empty())
-            .filter_map(|pair| {
-                let mut parts = pair.split('=');
-                Some((
-                    parts.next()?.to_string(),
-                    parts.next().unwrap_or("").to_string(),
-                ))
-            })
-            .collect()
+    // Whether a GET or HEAD for a static file can be answered with 304.
+    // If-None-Match takes precedence; If-Modified-Since is only checked
+    // without it, at the one-second precision of HTTP dates.
+    fn is_not_modified(req: &Request, etag: &str, modified: Option<Duration>) -> bool {
+        if !matches!(req.method, Method::GET | Method::HEAD) {
+            return false;
+        }
+        if let Some(if_none_match) = req.get_header("if-none-match") {
+            return Self::etag_matches(if_none_match, etag);
+        }
+        let since = req
+            .get_header("if-modified-since")
+            .and_then(|since| httpdate::parse_http_date(since).ok())
+            .and_then(|since| since.duration_since(SystemTime::UNIX_EPOCH).ok());
+        match (since, modified) {
+            (Some(since), Some(modified)) => modified.as_secs() <= since.as_secs(),
+            _ => false,
+        }
     }
 
-    fn match_dynamic_path(&self, pattern: &str, path: &str) -> Option<HashMap<String, String>> {
-        let pattern_parts: Vec<&str> = pattern.split('/').collect();
-        let path_parts: Vec<&str> = path.split('/').collect();
+    // Whether a handler's response is unchanged from the client's copy, by
+    // its ETag or else its Last-Modified, with the same precedence as
+    // `is_not_modified`
+    fn response_not_modified(response: &Response, if_none_match: Option<&str>, if_modified_since: Option<&str>) -> bool {
+        let header = |name: &str| {
+            response
+                .headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        if let Some(if_none_match) = if_none_match {
+            return header("etag").is_some_and(|etag| Self::etag_matches(if_none_match, etag));
+        }
+        let parse = |date: &str| httpdate::parse_http_date(date).ok();
+        match (if_modified_since.and_then(parse), header("last-modified").and_then(parse)) {
+            (Some(since), Some(modified)) => modified <= since,
+            _ => false,
+        }
+    }
 
-        if pattern_parts.len() != path_parts.len() {
+    // Parses a single `bytes=start-end`, `bytes=start-` or `bytes=-suffix`
+    // range against a file of `len` bytes. Malformed and multi-part ranges
+    // return None so the whole file is served.
+    fn parse_range(range: &str, len: u64) -> Option<ByteRange> {
+        let spec = range.trim().strip_prefix("bytes=")?;
+        if spec.contains(',') {
             return None;
         }
+        let (start, end) = spec.split_once('-')?;
+        let (start, end) = (start.trim(), end.trim());
 
-        let mut params = HashMap::new();
-
-        for (pattern_part, path_part) in pattern_parts.iter().zip(path_parts.iter()) {
-            if pattern_part.starts_with(':') {
-                params.insert(pattern_part[1..].to_string(), path_part.to_string());
-            } else if pattern_part != path_part {
-                return None;
+        if start.is_empty() {
+            let suffix: u64 = end.parse().ok()?;
+            if suffix == 0 || len == 0 {
+                return Some(ByteRange::Unsatisfiable);
             }
+            return Some(ByteRange::Satisfiable(len.saturating_sub(suffix), len - 1));
         }
 
-        Some(params)
+        let start: u64 = start.parse().ok()?;
+        let end = match end {
+            "" => None,
+            end => Some(end.parse::<u64>().ok()?),
+        };
+        if end.is_some_and(|end| end < start) {
+            return None;
+        }
+        if start >= len {
+            return Some(ByteRange::Unsatisfiable);
+        }
+        Some(ByteRange::Satisfiable(start, end.unwrap_or(len - 1).min(len - 1)))
     }
+
+    /// Compares an `If-None-Match` header value against an entity tag using
+    /// the weak comparison function (RFC 9110, section 13.1.2): the `W/`
+    /// prefix is ignored on both sides and `*` matches any current entity.
+    fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+        let if_none_match = if_none_match.trim();
+        if if_none_match == "*" {
+            return true;
+        }
+        let strip_weak = |tag: &str| {
+            let tag = tag.trim();
+            tag.strip_prefix("W/").unwrap_or(tag).to_string()
+        };
+        let etag = strip_weak(etag);
+        if_none_match
+            .split(',')
+            .map(strip_weak)
+            .any(|candidate| candidate == etag)
+    }
+
+    // Content type for bodies sent without a Content-Type header
+    fn fallback_content_type(&self, body: &[u8]) -> String {
+        if self.sniff_content_type && serde_json::from_slice::<serde::de::IgnoredAny>(body).is_ok() {
+            return "application/json".to_string();
+        }
+        self.default_content_type
+            .clone()
+            .unwrap_or_else(|| "none".to_string())
+    }
+}
+
+// A parsed `Range` header for a static file
+enum ByteRange {
+    // Inclusive offsets within the file
+    Satisfiable(u64, u64),
+    Unsatisfiable,
 }
@@ -1,4 +1,5 @@
 use moka::future::Cache;
+use std::future::Future;
 use std::hash::Hash;
 use std::time::Duration;
 
@@ -32,6 +33,57 @@ where
         self.cache.insert(key, value).await;
     }
 
+    /// Returns the cached value for `key`, computing and storing it with
+    /// `init` on a miss. Concurrent misses for the same key share a single
+    /// `init` call.
+    ///
+    /// This is the building block for memoized handlers: register the cache
+    /// with `Server::plugins` and look it up from the request.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axeon::cache::JsonCache;
+    /// use axeon::{json, Request, Response, Server};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// let mut app = Server::new();
+    /// app.plugins(JsonCache::<String>::new(1_000, Duration::from_secs(60)));
+    /// app.get("/report/:id", |req: Request| async move {
+    ///     let cache = req.plugins.get::<JsonCache<String>>().unwrap();
+    ///     let id = req.params["id"].clone();
+    ///     let report = cache
+    ///         .get_or_insert_with(id.clone(), || async move { json!({ "id": id }) })
+    ///         .await;
+    ///     Response::ok(&report)
+    /// });
+    ///
+    /// // The computation only runs on a cache miss
+    /// let misses = Arc::new(AtomicUsize::new(0));
+    /// let cache = JsonCache::<String>::new(1_000, Duration::from_secs(60));
+    /// tokio::runtime::Runtime::new().unwrap().block_on(async {
+    ///     for _ in 0..2 {
+    ///         let misses = misses.clone();
+    ///         cache
+    ///             .get_or_insert_with("key".to_string(), || async move {
+    ///                 misses.fetch_add(1, Ordering::SeqCst);
+    ///                 json!("computed")
+    ///             })
+    ///             .await;
+    ///     }
+    /// });
+    /// assert_eq!(misses.load(Ordering::SeqCst), 1);
+    /// ```
+    pub async fn get_or_insert_with<F, Fut>(&self, key: K, init: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        self.cache.get_with(key, init()).await
+    }
+
     pub async fn remove(&self, key: &K) where K: Hash {
         self.cache.remove(key).await;
     }
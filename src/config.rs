@@ -0,0 +1,91 @@
+//! Declarative server settings that can be loaded from the environment or
+//! any serde format and applied with `Server::configure`.
+
+use crate::error::ServerError;
+use crate::http::query::QueryDeserializer;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+#[cfg(feature = "tls")]
+use std::path::PathBuf;
+
+/// Server settings as data. Every field is optional; `Server::configure`
+/// only applies the ones that are set and leaves the rest as they are.
+/// Durations are in whole seconds.
+///
+/// It implements `Deserialize`, so it can be read from a JSON or TOML file
+/// with the matching serde crate, or from environment variables with
+/// `from_env`.
+///
+/// ```rust
+/// use axeon::{Server, ServerSettings};
+/// use std::time::Duration;
+///
+/// let settings: ServerSettings = serde_json::from_str(r#"{
+///     "max_connections": 64,
+///     "keep_alive_secs": 15,
+///     "request_timeout_secs": 5,
+///     "static_dir": "public"
+/// }"#).unwrap();
+///
+/// let mut app = Server::new();
+/// app.configure(settings);
+/// assert_eq!(app.max_connections, 64);
+/// assert_eq!(app.keep_alive, Duration::from_secs(15));
+/// assert_eq!(app.request_timeout, Duration::from_secs(5));
+/// // Unset fields keep their defaults
+/// assert_eq!(app.drain_timeout, Duration::from_secs(10));
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    pub max_connections: Option<usize>,
+    pub keep_alive_secs: Option<u64>,
+    pub request_timeout_secs: Option<u64>,
+    pub drain_timeout_secs: Option<u64>,
+    pub max_body_size: Option<usize>,
+    pub max_in_flight: Option<usize>,
+    pub reject_when_busy: Option<bool>,
+    /// Only takes effect if configured before any route is registered
+    pub strict_slash: Option<bool>,
+    pub default_content_type: Option<String>,
+    pub static_dir: Option<String>,
+    pub static_stream_threshold: Option<u64>,
+    #[cfg(feature = "tls")]
+    pub tls_cert_file: Option<PathBuf>,
+    #[cfg(feature = "tls")]
+    pub tls_key_file: Option<PathBuf>,
+    /// Enables client certificate checks; needs both TLS files as well
+    #[cfg(feature = "tls")]
+    pub tls_client_ca_file: Option<PathBuf>,
+}
+
+impl ServerSettings {
+    /// Reads settings from environment variables named by `prefix` plus the
+    /// upper-cased field name, e.g. `APP_MAX_CONNECTIONS` for the prefix
+    /// `"APP_"`. Variables that don't match a field are ignored.
+    ///
+    /// ```rust
+    /// use axeon::ServerSettings;
+    ///
+    /// std::env::set_var("DOCTEST_MAX_BODY_SIZE", "4096");
+    /// std::env::set_var("DOCTEST_STRICT_SLASH", "true");
+    /// let settings = ServerSettings::from_env("DOCTEST_").unwrap();
+    /// assert_eq!(settings.max_body_size, Some(4096));
+    /// assert_eq!(settings.strict_slash, Some(true));
+    /// assert_eq!(settings.max_connections, None);
+    ///
+    /// std::env::set_var("DOCTEST_MAX_BODY_SIZE", "lots");
+    /// assert!(ServerSettings::from_env("DOCTEST_").is_err());
+    /// ```
+    pub fn from_env(prefix: &str) -> Result<Self, ServerError> {
+        let mut fields = Map::new();
+        for (name, value) in std::env::vars() {
+            if let Some(field) = name.strip_prefix(prefix) {
+                fields.insert(field.to_ascii_lowercase(), Value::String(value));
+            }
+        }
+        // Values are all strings, so they are parsed like query parameters
+        Self::deserialize(QueryDeserializer(Value::Object(fields)))
+            .map_err(|err| ServerError::ParseError(err.to_string()))
+    }
+}
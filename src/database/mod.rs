@@ -11,6 +11,7 @@ pub enum DatabaseError {
     PoolExhausted,
     ConnectionFailed,
     InvalidConnection,
+    IoError(std::io::Error),
 }
 
 pub trait Connection: Send + Sync {
@@ -18,11 +19,69 @@ pub trait Connection: Send + Sync {
     fn close(&mut self);
 }
 
+/// A connection that consumes a byte stream, such as a large object or
+/// bulk upload, used with `DatabasePlugin::pipe`.
+pub trait SinkConnection: Connection {
+    fn write(&mut self, chunk: &[u8]) -> Result<(), DatabaseError>;
+
+    /// Called once all chunks were written
+    fn finish(&mut self) -> Result<(), DatabaseError> {
+        Ok(())
+    }
+}
+
+/// Settings for a `ConnectionPool`.
+///
+/// With `test_on_borrow` set, a pooled connection is checked with
+/// `Connection::is_valid` before it is handed out, but only if it has been
+/// idle for at least `validation_interval`. Raise the interval when
+/// validation is an expensive round-trip to the database.
+///
+/// # Example
+///
+/// ```rust
+/// use axeon::database::{Connection, ConnectionPool, PoolConfig};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// struct Conn(Arc<AtomicUsize>);
+///
+/// impl Connection for Conn {
+///     fn is_valid(&self) -> bool {
+///         self.0.fetch_add(1, Ordering::SeqCst);
+///         true
+///     }
+///     fn close(&mut self) {}
+/// }
+///
+/// let checks = Arc::new(AtomicUsize::new(0));
+/// let counter = checks.clone();
+/// let config = PoolConfig {
+///     min_idle: 1,
+///     validation_interval: Duration::from_secs(30),
+///     ..PoolConfig::default()
+/// };
+/// let pool = ConnectionPool::new(config, move || Ok(Conn(counter.clone())));
+/// let created = checks.load(Ordering::SeqCst);
+///
+/// // The idle connection was just created, so it's not validated again
+/// let _conn = pool.get().unwrap();
+/// assert_eq!(checks.load(Ordering::SeqCst), created);
+/// ```
 pub struct PoolConfig {
     pub max_size: usize,
     pub min_idle: usize,
     pub max_lifetime: Duration,
     pub idle_timeout: Duration,
+    /// Validate pooled connections before handing them out
+    pub test_on_borrow: bool,
+    /// Minimum idle time before a borrowed connection is validated
+    pub validation_interval: Duration,
+    /// How long to wait before retrying after the connection factory fails.
+    /// Doubles with each consecutive failure, up to `max_connect_backoff`.
+    pub connect_backoff: Duration,
+    pub max_connect_backoff: Duration,
 }
 
 impl Default for PoolConfig {
@@ -32,6 +91,10 @@ impl Default for PoolConfig {
             min_idle: 2,
             max_lifetime: Duration::from_secs(30 * 60), // 30 minutes
             idle_timeout: Duration::from_secs(10 * 60), // 10 minutes
+            test_on_borrow: true,
+            validation_interval: Duration::ZERO, // validate on every checkout
+            connect_backoff: Duration::from_millis(100),
+            max_connect_backoff: Duration::from_secs(30),
         }
     }
 }
@@ -42,10 +105,52 @@ struct PooledConnection<C: Connection> {
     last_used_at: Instant,
 }
 
+// Consecutive factory failures and when the next attempt is allowed
+#[derive(Default)]
+struct Backoff {
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+/// A pool of reusable connections.
+///
+/// When the connection factory fails, `get` returns its error instead of
+/// `PoolExhausted`. Further attempts within the backoff window fail fast with
+/// `ConnectionFailed` without calling the factory, so a database that is down
+/// isn't hammered with reconnects.
+///
+/// # Example
+///
+/// ```rust
+/// use axeon::database::{Connection, ConnectionPool, DatabaseError, PoolConfig};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// struct Conn;
+///
+/// impl Connection for Conn {
+///     fn is_valid(&self) -> bool { true }
+///     fn close(&mut self) {}
+/// }
+///
+/// let attempts = Arc::new(AtomicUsize::new(0));
+/// let counter = attempts.clone();
+/// let config = PoolConfig { min_idle: 0, ..PoolConfig::default() };
+/// let pool = ConnectionPool::new(config, move || -> Result<Conn, DatabaseError> {
+///     counter.fetch_add(1, Ordering::SeqCst);
+///     Err(DatabaseError::ConnectionFailed)
+/// });
+///
+/// assert!(matches!(pool.get(), Err(DatabaseError::ConnectionFailed)));
+/// // Retrying right away backs off instead of calling the factory again
+/// assert!(matches!(pool.get(), Err(DatabaseError::ConnectionFailed)));
+/// assert_eq!(attempts.load(Ordering::SeqCst), 1);
+/// ```
 pub struct ConnectionPool<C: Connection> {
     connections: Arc<Mutex<VecDeque<PooledConnection<C>>>>,
     config: PoolConfig,
     create_connection: Arc<dyn Fn() -> Result<C, DatabaseError> + Send + Sync>,
+    backoff: Mutex<Backoff>,
 }
 
 impl<C: Connection + 'static> ConnectionPool<C> {
@@ -58,15 +163,13 @@ impl<C: Connection + 'static> ConnectionPool<C> {
             connections: connections.clone(),
             config,
             create_connection: Arc::new(create_fn),
+            backoff: Mutex::new(Backoff::default()),
         };
 
         // Initialize minimum idle connections
-        {
-            let mut guard = connections.lock().unwrap();
-            for _ in 0..pool.config.min_idle {
-                if let Ok(conn) = pool.create_new_connection() {
-                    guard.push_back(conn);
-                }
+        for _ in 0..pool.config.min_idle {
+            if let Ok(conn) = pool.create_new_connection() {
+                connections.lock().unwrap().push_back(conn);
             }
         }
 
@@ -90,21 +193,58 @@ impl<C: Connection + 'static> ConnectionPool<C> {
 
         // Try to get an existing connection
         if let Some(mut pooled) = connections.pop_front() {
-            if pooled.connection.is_valid() {
+            let needs_validation = self.config.test_on_borrow
+                && now.duration_since(pooled.last_used_at) >= self.config.validation_interval;
+            if !needs_validation || pooled.connection.is_valid() {
                 pooled.last_used_at = now;
                 return Ok(pooled.connection);
             }
             pooled.connection.close();
         }
 
-        // Create new connection if under max_size
-        if connections.len() < self.config.max_size {
-            if let Ok(conn) = self.create_new_connection() {
-                return Ok(conn.connection);
-            }
+        if connections.len() >= self.config.max_size {
+            return Err(DatabaseError::PoolExhausted);
+        }
+        // The factory may be slow, so other callers can use the pool meanwhile
+        drop(connections);
+        let mut connection = self.connect()?;
+
+        // Releases while the factory ran may have filled the pool
+        if self.connections.lock().unwrap().len() >= self.config.max_size {
+            connection.close();
+            return Err(DatabaseError::PoolExhausted);
+        }
+        Ok(connection)
+    }
+
+    // Creates a connection unless a recent failure is still backing off. The
+    // backoff lock isn't held while the factory runs, so one slow attempt
+    // doesn't hold up other callers.
+    fn connect(&self) -> Result<C, DatabaseError> {
+        let retry_at = self.backoff.lock().unwrap().retry_at;
+        if retry_at.is_some_and(|retry_at| Instant::now() < retry_at) {
+            return Err(DatabaseError::ConnectionFailed);
         }
 
-        Err(DatabaseError::PoolExhausted)
+        let created = self.create_new_connection();
+        let mut backoff = self.backoff.lock().unwrap();
+        match created {
+            Ok(pooled) => {
+                *backoff = Backoff::default();
+                Ok(pooled.connection)
+            }
+            Err(err) => {
+                let exponent = backoff.failures.min(16);
+                backoff.failures += 1;
+                let delay = self
+                    .config
+                    .connect_backoff
+                    .saturating_mul(1 << exponent)
+                    .min(self.config.max_connect_backoff);
+                backoff.retry_at = Some(Instant::now() + delay);
+                Err(err)
+            }
+        }
     }
 
     pub fn release(&self, connection: C) {
@@ -134,4 +274,49 @@ impl<C: Connection + 'static> ConnectionPool<C> {
             last_used_at: Instant::now(),
         })
     }
+}
+
+impl<C: Connection> ConnectionPool<C> {
+    /// Closes and removes every idle connection, e.g. on shutdown.
+    /// Connections that are checked out are unaffected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axeon::database::{Connection, ConnectionPool, PoolConfig};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct Conn(Arc<AtomicUsize>);
+    ///
+    /// impl Connection for Conn {
+    ///     fn is_valid(&self) -> bool { true }
+    ///     fn close(&mut self) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let closed = Arc::new(AtomicUsize::new(0));
+    /// let counter = closed.clone();
+    /// let config = PoolConfig { min_idle: 3, ..PoolConfig::default() };
+    /// let pool = ConnectionPool::new(config, move || Ok(Conn(counter.clone())));
+    ///
+    /// pool.close_all();
+    /// assert_eq!(closed.load(Ordering::SeqCst), 3);
+    /// ```
+    pub fn close_all(&self) {
+        let mut connections = match self.connections.lock() {
+            Ok(connections) => connections,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        for mut pooled in connections.drain(..) {
+            pooled.connection.close();
+        }
+    }
+}
+
+impl<C: Connection> Drop for ConnectionPool<C> {
+    fn drop(&mut self) {
+        self.close_all();
+    }
 }
\ No newline at end of file
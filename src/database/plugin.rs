@@ -1,5 +1,6 @@
 use std::sync::Arc;
-use crate::database::{Connection, ConnectionPool, PoolConfig, DatabaseError};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use crate::database::{Connection, ConnectionPool, PoolConfig, DatabaseError, SinkConnection};
 
 pub struct DatabasePlugin<C: Connection> {
     pool: Arc<ConnectionPool<C>>,
@@ -22,6 +23,79 @@ impl<C: Connection + 'static> DatabasePlugin<C> {
     pub fn release_connection(&self, connection: C) {
         self.pool.release(connection);
     }
+
+    /// Closes all idle pooled connections
+    pub fn close_all(&self) {
+        self.pool.close_all();
+    }
+}
+
+impl<C: SinkConnection + 'static> DatabasePlugin<C> {
+    /// Copies everything from `reader` into a pooled connection chunk by
+    /// chunk and returns the number of bytes written. The connection is
+    /// released on success and closed on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axeon::database::{Connection, DatabaseError, DatabasePlugin, PoolConfig, SinkConnection};
+    ///
+    /// struct BlobStore {
+    ///     received: Vec<u8>,
+    /// }
+    ///
+    /// impl Connection for BlobStore {
+    ///     fn is_valid(&self) -> bool { true }
+    ///     fn close(&mut self) {}
+    /// }
+    ///
+    /// impl SinkConnection for BlobStore {
+    ///     fn write(&mut self, chunk: &[u8]) -> Result<(), DatabaseError> {
+    ///         self.received.extend_from_slice(chunk);
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let plugin = DatabasePlugin::new(PoolConfig::default(), || Ok(BlobStore { received: Vec::new() }));
+    /// let upload = vec![7u8; 100_000];
+    /// let written = tokio::runtime::Runtime::new()
+    ///     .unwrap()
+    ///     .block_on(plugin.pipe(upload.as_slice()))
+    ///     .unwrap();
+    /// assert_eq!(written, upload.len());
+    /// ```
+    pub async fn pipe<R>(&self, mut reader: R) -> Result<usize, DatabaseError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut connection = self.get_connection()?;
+        let mut buffer = vec![0u8; 8192];
+        let mut total = 0;
+
+        let result = async {
+            loop {
+                let read = reader.read(&mut buffer).await.map_err(DatabaseError::IoError)?;
+                if read == 0 {
+                    break;
+                }
+                connection.write(&buffer[..read])?;
+                total += read;
+            }
+            connection.finish()
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                self.release_connection(connection);
+                Ok(total)
+            }
+            Err(err) => {
+                connection.close();
+                Err(err)
+            }
+        }
+    }
 }
 
 impl<C: Connection> Clone for DatabasePlugin<C> {
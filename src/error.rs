@@ -1,5 +1,7 @@
 use std::io;
 use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub enum ServerError {
@@ -8,25 +10,47 @@ pub enum ServerError {
     ValidationError(String),
     NotFound,
     BadRequest(String),
-    Unauthorized(String),
+    /// 401, with the `WWW-Authenticate` challenge to send, e.g. `Bearer`
+    Unauthorized { message: String, challenge: String },
     Forbidden(String),
     InternalError(String),
     Conflict(String),
     PanicError(String),
-    TooManyRequests,
+    /// 429, with the `Retry-After` delay to send if one is known
+    TooManyRequests { retry_after: Option<Duration> },
+    Timeout,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    NotImplemented,
+    ServiceUnavailable,
 }
 
 impl ServerError {
+    /// Returns the HTTP status code this error is answered with.
+    ///
+    /// ```rust
+    /// use axeon::ServerError;
+    ///
+    /// assert_eq!(ServerError::PayloadTooLarge.status_code(), 413);
+    /// assert_eq!(ServerError::UnsupportedMediaType.status_code(), 415);
+    /// assert_eq!(ServerError::NotImplemented.status_code(), 501);
+    /// assert_eq!(ServerError::NotImplemented.to_string(), "Not implemented");
+    /// ```
     pub fn status_code(&self) -> u16 {
         match self {
             ServerError::BadRequest(_) => 400,
-            ServerError::Unauthorized(_) => 401,
+            ServerError::Unauthorized { .. } => 401,
             ServerError::Forbidden(_) => 403,
             ServerError::NotFound => 404,
             ServerError::Conflict(_) => 409,
+            ServerError::PayloadTooLarge => 413,
+            ServerError::UnsupportedMediaType => 415,
             ServerError::ParseError(_) => 422,
             ServerError::ValidationError(_) => 422,
-            ServerError::TooManyRequests => 429,
+            ServerError::TooManyRequests { .. } => 429,
+            ServerError::NotImplemented => 501,
+            ServerError::ServiceUnavailable => 503,
+            ServerError::Timeout => 504,
             ServerError::IoError(_)
             | ServerError::InternalError(_)
             | ServerError::PanicError(_) => 500,
@@ -34,6 +58,88 @@ impl ServerError {
     }
 }
 
+/// Cloneable summary of a `ServerError`, for middleware that needs to keep
+/// or pass along error details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorInfo {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ServerError {
+    /// Returns a cloneable summary of this error's status and message.
+    ///
+    /// ```rust
+    /// use axeon::ServerError;
+    ///
+    /// let err = ServerError::IoError(std::io::Error::other("disk full"));
+    /// let info = err.describe();
+    /// let copy = info.clone();
+    /// assert_eq!(copy.status, 500);
+    /// assert_eq!(copy.message, "IO error: disk full");
+    ///
+    /// let text = || "x".to_string();
+    /// let errors = [
+    ///     (ServerError::ParseError(text()), 422, "Parse error: x"),
+    ///     (ServerError::ValidationError(text()), 422, "Validation error: x"),
+    ///     (ServerError::NotFound, 404, "Not found"),
+    ///     (ServerError::BadRequest(text()), 400, "Bad request: x"),
+    ///     (ServerError::Unauthorized { message: text(), challenge: "Bearer".to_string() }, 401, "Unauthorized: x"),
+    ///     (ServerError::Forbidden(text()), 403, "Forbidden: x"),
+    ///     (ServerError::InternalError(text()), 500, "Internal error: x"),
+    ///     (ServerError::Conflict(text()), 409, "Conflict: x"),
+    ///     (ServerError::PanicError(text()), 500, "Panic: x"),
+    ///     (ServerError::TooManyRequests { retry_after: None }, 429, "Too many requests"),
+    ///     (ServerError::Timeout, 504, "Request timed out"),
+    ///     (ServerError::PayloadTooLarge, 413, "Payload too large"),
+    ///     (ServerError::UnsupportedMediaType, 415, "Unsupported media type"),
+    ///     (ServerError::NotImplemented, 501, "Not implemented"),
+    ///     (ServerError::ServiceUnavailable, 503, "Service unavailable"),
+    /// ];
+    /// for (err, status, message) in errors {
+    ///     let info = err.describe().clone();
+    ///     assert_eq!((info.status, info.message.as_str()), (status, message));
+    /// }
+    /// ```
+    pub fn describe(&self) -> ErrorInfo {
+        ErrorInfo {
+            status: self.status_code(),
+            message: self.to_string(),
+        }
+    }
+
+    /// Headers the response for this error should carry: the
+    /// authentication challenge for 401 and the retry delay for 429, in
+    /// whole seconds rounded up.
+    ///
+    /// ```rust
+    /// use axeon::ServerError;
+    /// use std::time::Duration;
+    ///
+    /// let err = ServerError::Unauthorized {
+    ///     message: "Missing credentials".to_string(),
+    ///     challenge: "Basic realm=\"admin\"".to_string(),
+    /// };
+    /// assert_eq!(err.headers(), [("WWW-Authenticate".to_string(), "Basic realm=\"admin\"".to_string())]);
+    ///
+    /// let err = ServerError::TooManyRequests { retry_after: Some(Duration::from_millis(2500)) };
+    /// assert_eq!(err.headers(), [("Retry-After".to_string(), "3".to_string())]);
+    /// assert!(ServerError::TooManyRequests { retry_after: None }.headers().is_empty());
+    /// ```
+    pub fn headers(&self) -> Vec<(String, String)> {
+        match self {
+            ServerError::Unauthorized { challenge, .. } => {
+                vec![("WWW-Authenticate".to_string(), challenge.clone())]
+            }
+            ServerError::TooManyRequests { retry_after: Some(wait) } => {
+                let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+                vec![("Retry-After".to_string(), seconds.max(1).to_string())]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
 impl fmt::Display for ServerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -42,12 +148,17 @@ impl fmt::Display for ServerError {
             ServerError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             ServerError::NotFound => write!(f, "Not found"),
             ServerError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
-            ServerError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            ServerError::Unauthorized { message, .. } => write!(f, "Unauthorized: {}", message),
             ServerError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             ServerError::Conflict(msg) => write!(f, "Conflict: {}", msg),
             ServerError::InternalError(msg) => write!(f, "Internal error: {}", msg),
             ServerError::PanicError(msg) => write!(f, "Panic: {}", msg),
-            ServerError::TooManyRequests => write!(f, "Too many requests"),
+            ServerError::TooManyRequests { .. } => write!(f, "Too many requests"),
+            ServerError::Timeout => write!(f, "Request timed out"),
+            ServerError::PayloadTooLarge => write!(f, "Payload too large"),
+            ServerError::UnsupportedMediaType => write!(f, "Unsupported media type"),
+            ServerError::NotImplemented => write!(f, "Not implemented"),
+            ServerError::ServiceUnavailable => write!(f, "Service unavailable"),
         }
     }
 }
@@ -68,3 +179,23 @@ impl From<io::Error> for ServerError {
 }
 
 pub type ServerResult<T> = Result<T, ServerError>;
+
+/// Errors from route setup registered with `Server::setup` or from invalid
+/// server configuration, returned by `Server::build` and by `listen` before
+/// it starts accepting.
+#[derive(Debug, Clone)]
+pub struct SetupError {
+    pub errors: Vec<Arc<dyn std::error::Error + Send + Sync>>,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} route setup error(s)", self.errors.len())?;
+        for (i, err) in self.errors.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { ";" }, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SetupError {}
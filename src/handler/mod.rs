@@ -1,7 +1,10 @@
 use crate::error::ServerResult;
+use crate::http::json::Json;
 use crate::http::Request;
 use crate::http::Response;
 use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::future::Future;
 
 pub(crate) type HttpResponse = ServerResult<Response>;
@@ -16,12 +19,60 @@ impl<F: Future<Output = HttpResponse> + Send + 'static> IntoResponse for F {
     }
 }
 
+/// Lets a handler return `Json(value)` directly, answered as a 200 JSON
+/// response.
+///
+/// ```rust,no_run
+/// use axeon::{json, Json, Server};
+///
+/// let mut app = Server::new();
+/// app.get("/version", |_req| Json(json!({ "version": "0.2.0" })));
+/// app.listen("127.0.0.1:3000").unwrap();
+/// ```
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response_future(self) -> BoxFuture<'static, HttpResponse> {
+        Box::pin(futures::future::ready(self.into_response()))
+    }
+}
+
 pub trait Handler: Send + Sync + 'static {
     fn handle(&self, req: Request) -> BoxFuture<'static, HttpResponse>;
 
     fn dyn_clone<'s>(&self) -> Box<dyn Handler + 's>
     where
         Self: 's;
+
+    /// Wraps this handler so `f` can adjust every successful response it
+    /// returns, e.g. to tag the variant in an A/B split. Errors pass
+    /// through unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Handler, Request, Response, Server, ServerError};
+    ///
+    /// async fn checkout(_req: Request) -> Result<Response, ServerError> {
+    ///     Response::text("checkout")
+    /// }
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/checkout", checkout.map_response(|mut response| {
+    ///     response.header("X-Variant", "b");
+    ///     response
+    /// }));
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    fn map_response<M>(self, f: M) -> impl Fn(Request) -> BoxFuture<'static, HttpResponse> + Send + Sync + Clone + 'static
+    where
+        Self: Sized + Clone,
+        M: Fn(Response) -> Response + Send + Sync + Clone + 'static,
+    {
+        move |req: Request| {
+            let response = self.handle(req);
+            let f = f.clone();
+            Box::pin(async move { response.await.map(f) })
+        }
+    }
 }
 
 impl Clone for Box<dyn Handler> {
@@ -46,3 +97,44 @@ where
         Box::new((*self).clone())
     }
 }
+
+/// Adapts a function taking and returning `Json` into a route handler.
+/// The request body is parsed into `In` (400 on failure) and the returned
+/// `Out` is sent as a 200 JSON response.
+///
+/// # Example
+///
+/// ```rust
+/// use axeon::{json_handler, Json, Server};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize)]
+/// struct Greet {
+///     name: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Greeting {
+///     message: String,
+/// }
+///
+/// let mut app = Server::new();
+/// app.post("/greet", json_handler(|Json(greet): Json<Greet>| async move {
+///     Json(Greeting { message: format!("Hello, {}!", greet.name) })
+/// }));
+/// ```
+pub fn json_handler<F, Fut, In, Out>(handler: F) -> impl Fn(Request) -> BoxFuture<'static, HttpResponse> + Send + Sync + Clone + 'static
+where
+    F: Fn(Json<In>) -> Fut + Send + Sync + Clone + 'static,
+    Fut: Future<Output = Json<Out>> + Send + 'static,
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize,
+{
+    move |req: Request| {
+        let handler = handler.clone();
+        Box::pin(async move {
+            let input = Json::<In>::from_request(&req)?;
+            handler(input).await.into_response()
+        })
+    }
+}
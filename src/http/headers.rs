@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::ops::Index;
+
+type Entries<'a> = std::slice::Iter<'a, (String, String)>;
+
+/// Response headers, kept in the order they were added.
+///
+/// Names are matched without regard to case, and a name can have several
+/// values, each sent as its own header line, which is how `Set-Cookie` has
+/// to be sent.
+///
+/// ```rust
+/// use axeon::HeaderMap;
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert("Content-Type", "text/plain");
+/// headers.append("Set-Cookie", "a=1");
+/// headers.append("set-cookie", "b=2");
+/// assert_eq!(headers["content-type"], "text/plain");
+/// assert_eq!(headers.get_all("Set-Cookie"), ["a=1", "b=2"]);
+///
+/// headers.insert("Set-Cookie", "c=3");
+/// assert_eq!(headers.get_all("Set-Cookie"), ["c=3"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the first value of a header
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns every value of a header, in the order they were added
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Sets a header, replacing all of its existing values
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        let (name, value) = (name.into(), value.into());
+        match self.entries.iter().position(|(key, _)| key.eq_ignore_ascii_case(&name)) {
+            // The first value is replaced in place so the header keeps its position
+            Some(first) => {
+                let mut index = 0;
+                self.entries.retain(|(key, _)| {
+                    index += 1;
+                    index - 1 <= first || !key.eq_ignore_ascii_case(&name)
+                });
+                self.entries[first] = (name, value);
+            }
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    /// Adds a value for a header, keeping the existing ones
+    pub fn append<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        self.entries.push((name.into(), value.into()));
+    }
+
+    /// Removes every value of a header, returning the first
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let first = self.get(name).cloned();
+        self.entries.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        first
+    }
+
+    pub fn retain<F: FnMut(&str, &str) -> bool>(&mut self, mut keep: F) {
+        self.entries.retain(|(name, value)| keep(name, value));
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(name, _)| name)
+    }
+
+    /// Number of header lines, counting each value separately
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Iterator over the headers of a `HeaderMap`, one item per value
+pub struct Iter<'a>(Entries<'a>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(name, value)| (name, value))
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a String, &'a String);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl Index<&str> for HeaderMap {
+    type Output = String;
+
+    fn index(&self, name: &str) -> &String {
+        self.get(name).unwrap_or_else(|| panic!("no header named {}", name))
+    }
+}
+
+// Like `HashMap::extend`, each header replaces any value of the same name
+impl Extend<(String, String)> for HeaderMap {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, headers: I) {
+        for (name, value) in headers {
+            self.insert(name, value);
+        }
+    }
+}
+
+impl FromIterator<(String, String)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(headers: I) -> Self {
+        let mut map = HeaderMap::new();
+        map.extend(headers);
+        map
+    }
+}
+
+impl From<HashMap<String, String>> for HeaderMap {
+    fn from(headers: HashMap<String, String>) -> Self {
+        headers.into_iter().collect()
+    }
+}
@@ -0,0 +1,86 @@
+use crate::error::ServerError;
+use crate::handler::HttpResponse;
+use crate::http::{Request, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Guards applied before a JSON body is deserialized. `Body::json` uses
+/// the defaults: no size limit of its own, since the body already fits in
+/// `Server::max_body_size`, and 64 levels of nesting, below the point where
+/// deeply nested input could exhaust the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonLimits {
+    /// Largest document accepted, in bytes (unlimited by default)
+    pub max_bytes: usize,
+    /// Deepest nesting of arrays and objects accepted
+    pub max_depth: usize,
+}
+
+impl Default for JsonLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: usize::MAX,
+            max_depth: 64,
+        }
+    }
+}
+
+impl JsonLimits {
+    // Checks the size and, with a single pass that skips over strings, the
+    // nesting depth without building anything
+    pub(crate) fn allows(&self, data: &[u8]) -> bool {
+        if data.len() > self.max_bytes {
+            return false;
+        }
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        for &byte in data {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    if depth > self.max_depth {
+                        return false;
+                    }
+                }
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+/// Typed JSON payload, usable both for parsing a request body and as a
+/// 200 JSON response. See `json_handler` for using it as a handler's
+/// argument and return type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> Json<T> {
+    /// Parses the request body, failing with a 400 if it isn't valid JSON
+    /// for `T`
+    pub fn from_request(req: &Request) -> Result<Self, ServerError> {
+        req.body
+            .json()
+            .map(Json)
+            .ok_or_else(|| ServerError::BadRequest("Invalid JSON body".to_string()))
+    }
+}
+
+impl<T: Serialize> Json<T> {
+    /// Serializes the value into a 200 JSON response
+    pub fn into_response(self) -> HttpResponse {
+        Response::ok(&self.0)
+    }
+}
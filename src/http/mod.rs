@@ -1,6 +1,11 @@
+pub(crate) mod headers;
+pub(crate) mod json;
+pub(crate) mod query;
 pub(crate) mod request;
 pub(crate) mod response;
+pub(crate) mod sse;
+pub(crate) mod timing;
 
 
-pub(crate) use request::{Request, Body, Method};
+pub(crate) use request::{Request, Body, ClientCertInfo, Method};
 pub(crate) use response::Response;
\ No newline at end of file
@@ -0,0 +1,109 @@
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, Deserializer, IntoDeserializer, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::Value;
+
+// Decoded key/value pairs of a query string in order. `?flag` maps to an
+// empty value, pairs with an empty key (`?=x`) are dropped and values keep
+// any further `=` characters. Keys and values are percent-decoded after the
+// split, so `%26` and `%3D` stay in the value.
+pub(crate) fn query_pairs(query: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    query.split('&').filter_map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        if key.is_empty() {
+            None
+        } else {
+            Some((decode_query_component(key), decode_query_component(value)))
+        }
+    })
+}
+
+// Form-style decoding: `+` is a space, invalid UTF-8 is replaced lossily
+fn decode_query_component(component: &str) -> String {
+    let component = component.replace('+', " ");
+    match urlencoding::decode(&component) {
+        Ok(decoded) => decoded.into_owned(),
+        Err(_) => String::from_utf8_lossy(&urlencoding::decode_binary(component.as_bytes())).into_owned(),
+    }
+}
+
+// Deserializes query values, which are all strings, into typed fields:
+// numbers and booleans are parsed from their string form and an empty
+// value is `None` for optional fields.
+pub(crate) struct QueryDeserializer(pub(crate) Value);
+
+macro_rules! parse_scalar {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self.0 {
+                Value::String(text) => match text.trim().parse() {
+                    Ok(parsed) => visitor.$visit(parsed),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&text), &visitor)),
+                },
+                other => other.$method(visitor),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for QueryDeserializer {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(items) => visitor.visit_seq(SeqDeserializer::new(items.into_iter().map(QueryDeserializer))),
+            Value::Object(map) => visitor.visit_map(MapDeserializer::new(
+                map.into_iter().map(|(key, value)| (key, QueryDeserializer(value))),
+            )),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    parse_scalar! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self.0 {
+            Value::Null => visitor.visit_none(),
+            Value::String(text) if text.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for QueryDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
@@ -1,7 +1,18 @@
+use crate::error::ServerError;
+use crate::http::json::JsonLimits;
+use crate::http::query::{query_pairs, QueryDeserializer};
+use crate::http::timing::ServerTiming;
 use crate::plugins::Plugins;
 use base64::Engine;
+use serde::de::DeserializeOwned;
 use serde_json::{json, Map, Value};
+#[cfg(feature = "tls")]
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
 
 #[derive(Eq, Hash, PartialEq, Copy, Clone, Debug)]
 pub enum Method {
@@ -31,6 +42,32 @@ impl Method {
             _ => Method::GET,
         }
     }
+
+    /// Returns true for methods that don't modify server state
+    /// (GET, HEAD, OPTIONS, TRACE).
+    ///
+    /// ```rust
+    /// use axeon::Method;
+    ///
+    /// assert!(Method::GET.is_safe());
+    /// assert!(!Method::POST.is_safe());
+    /// ```
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+    }
+
+    /// Returns true for methods where repeating a request has the same
+    /// effect as sending it once (the safe methods plus PUT and DELETE).
+    ///
+    /// ```rust
+    /// use axeon::Method;
+    ///
+    /// assert!(Method::PUT.is_idempotent());
+    /// assert!(!Method::PATCH.is_idempotent());
+    /// ```
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Method::PUT | Method::DELETE)
+    }
 }
 
 #[derive(Debug)]
@@ -68,19 +105,111 @@ impl Body {
         }
     }
 
+    /// Builds a body with an explicit content type, e.g. to construct
+    /// requests in tests.
+    ///
+    /// ```rust
+    /// use axeon::Body;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// let body = Body::with_content_type(br#"{"name":"alice"}"#.to_vec(), "application/json");
+    /// let user: User = body.json().unwrap();
+    /// assert_eq!(user.name, "alice");
+    /// ```
+    pub fn with_content_type(data: Vec<u8>, content_type: &str) -> Body {
+        Body {
+            content_type: content_type.to_string(),
+            data,
+        }
+    }
+
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    pub fn set_content_type(&mut self, content_type: &str) -> &mut Self {
+        self.content_type = content_type.to_string();
+        self
+    }
+
     pub fn as_string(&self) -> String {
         String::from_utf8_lossy(&self.data).to_string()
     }
 
+    /// Returns the raw body. A body sent with `Transfer-Encoding: chunked`
+    /// is reassembled from its chunks, and the connection stays usable for
+    /// the next request.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.post("/echo", |req| async move { Response::text(req.body.as_string()) });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
     pub fn as_bytes(&self) -> &[u8] {
         &self.data
     }
 
+    /// Deserializes a JSON body. Parameters such as `charset` in the
+    /// content type are ignored.
+    ///
+    /// Any body the server accepted, up to `Server::max_body_size`, is
+    /// parsed; only nesting deeper than 64 levels is refused, see
+    /// `JsonLimits`. Use `json_with_limits` for a smaller cap.
+    ///
+    /// ```rust
+    /// use axeon::{Body, Value};
+    ///
+    /// let body = Body::with_content_type(br#"{"ok":true}"#.to_vec(), "application/json; charset=utf-8");
+    /// assert_eq!(body.json::<Value>().unwrap()["ok"], true);
+    ///
+    /// let body = Body::with_content_type(b"[1]".to_vec(), "Application/JSON ");
+    /// assert!(body.json::<Vec<u8>>().is_some());
+    /// ```
     pub fn json<T>(&self) -> Option<T>
     where
         T: serde::de::DeserializeOwned,
     {
-        if self.content_type == "application/json" {
+        self.json_with_limits(JsonLimits::default())
+    }
+
+    /// Like `json`, but with explicit size and nesting limits. Bodies past
+    /// either limit return `None` without being deserialized.
+    ///
+    /// ```rust
+    /// use axeon::{Body, JsonLimits, Value};
+    ///
+    /// let nested = |depth: usize| {
+    ///     let array = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    ///     Body::with_content_type(array.into_bytes(), "application/json")
+    /// };
+    /// assert!(nested(200).json::<Value>().is_none());
+    /// assert!(nested(50).json::<Value>().is_some());
+    /// let strict = JsonLimits { max_depth: 32, ..JsonLimits::default() };
+    /// assert!(nested(50).json_with_limits::<Value>(strict).is_none());
+    ///
+    /// // Brackets inside strings don't count towards the depth
+    /// let body = Body::with_content_type(br#"{"brackets":"[[[[[[[["}"#.to_vec(), "application/json");
+    /// let shallow = JsonLimits { max_depth: 1, ..JsonLimits::default() };
+    /// assert!(body.json_with_limits::<Value>(shallow).is_some());
+    ///
+    /// let large = format!("[{}0]", "0,".repeat(1024));
+    /// let body = Body::with_content_type(large.into_bytes(), "application/json");
+    /// assert!(body.json::<Value>().is_some());
+    /// let small = JsonLimits { max_bytes: 1024, ..JsonLimits::default() };
+    /// assert!(body.json_with_limits::<Value>(small).is_none());
+    /// ```
+    pub fn json_with_limits<T>(&self, limits: JsonLimits) -> Option<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if Self::is_json_content_type(&self.media_type()) && limits.allows(&self.data) {
             // Use from_slice instead of converting to string first
             serde_json::from_slice(&self.data).ok()
         } else {
@@ -88,11 +217,68 @@ impl Body {
         }
     }
 
+    /// Parses a JSON body into a generic `Value`, for code such as proxies
+    /// or loggers that has no concrete type to deserialize into. Accepts
+    /// the same content types as `json`.
+    ///
+    /// ```rust
+    /// use axeon::{json, Body};
+    ///
+    /// let body = Body::with_content_type(
+    ///     br#"{"user":{"id":7,"tags":["a","b"]},"active":null}"#.to_vec(),
+    ///     "application/vnd.api+json",
+    /// );
+    /// assert_eq!(
+    ///     body.json_value(),
+    ///     Some(json!({ "user": { "id": 7, "tags": ["a", "b"] }, "active": null }))
+    /// );
+    /// ```
+    pub fn json_value(&self) -> Option<Value> {
+        self.json()
+    }
+
+    /// Parses a newline-delimited JSON (NDJSON) body one line at a time, so
+    /// a malformed line yields an error without affecting the others. Blank
+    /// lines are skipped. The content type is not checked.
+    ///
+    /// ```rust
+    /// use axeon::{Body, Value};
+    ///
+    /// let body = Body::with_content_type(
+    ///     b"{\"id\":1}\n{\"id\":\n{\"id\":3}\n".to_vec(),
+    ///     "application/x-ndjson",
+    /// );
+    /// let (ok, failed): (Vec<_>, Vec<_>) = body.ndjson::<Value>().partition(|item| item.is_ok());
+    /// assert_eq!(ok.len(), 2);
+    /// assert_eq!(failed.len(), 1);
+    /// ```
+    pub fn ndjson<'a, T>(&'a self) -> impl Iterator<Item = Result<T, serde_json::Error>> + 'a
+    where
+        T: serde::de::DeserializeOwned + 'a,
+    {
+        self.data
+            .split(|&byte| byte == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(serde_json::from_slice)
+    }
+
+    /// Deserializes a URL-encoded form body.
+    ///
+    /// ```rust
+    /// use axeon::{Body, Value};
+    ///
+    /// let body = Body::with_content_type(
+    ///     b"name=alice".to_vec(),
+    ///     "application/x-www-form-urlencoded; charset=UTF-8",
+    /// );
+    /// assert_eq!(body.x_www_form_urlencoded::<Value>().unwrap()["name"], "alice");
+    /// ```
     pub fn x_www_form_urlencoded<T>(&self) -> Option<T>
     where
         T: serde::de::DeserializeOwned,
     {
-        if self.content_type == "application/x-www-form-urlencoded" {
+        if self.media_type() == "application/x-www-form-urlencoded" {
             serde_json::from_value(Self::parse_urlencoded(&self.data).ok()?).ok()
         } else {
             None
@@ -103,7 +289,7 @@ impl Body {
     where
         T: serde::de::DeserializeOwned,
     {
-        if self.content_type.starts_with("multipart/form-data") {
+        if self.media_type() == "multipart/form-data" {
             serde_json::from_value(
                 Self::parse_multipart(&self.content_type, &self.data).ok()?
             ).ok()
@@ -112,6 +298,23 @@ impl Body {
         }
     }
 
+    // The content type without parameters, trimmed and lowercased
+    fn media_type(&self) -> String {
+        self.content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase()
+    }
+
+    // Accepts application/json plus the text/json and application/*+json aliases
+    fn is_json_content_type(content_type: &str) -> bool {
+        content_type == "application/json"
+            || content_type == "text/json"
+            || (content_type.starts_with("application/") && content_type.ends_with("+json"))
+    }
+
     fn parse_multipart(content_type: &str, body: &[u8]) -> Result<Value, MultipartError> {
         let boundary = Self::extract_boundary(content_type)?;
         let delimiter = format!("--{boundary}");
@@ -181,7 +384,7 @@ impl Body {
             start += pos + pattern.len();
 
             if start + end_pattern.len() <= body.len() 
-                && &body[start..start + end_pattern.len()] == end_pattern {
+                && body[start..start + end_pattern.len()] == end_pattern {
                 break;
             }
         }
@@ -201,7 +404,7 @@ impl Body {
 
         // Parse the key into parts and track if each part is an array index
         let parts: Vec<(String, bool)> = {
-            let raw_parts: Vec<&str> = key.split(|c| c == '[' || c == ']')
+            let raw_parts: Vec<&str> = key.split(['[', ']'])
                 .filter(|s| !s.is_empty())
                 .collect();
             
@@ -311,7 +514,7 @@ impl Body {
         }
 
         let (root_key, _is_array_root) = &parts[0];
-        let mut current = json.entry(root_key.clone())
+        let current = json.entry(root_key.clone())
             .or_insert_with(|| {
                 if parts.len() > 1 && parts[1].1 {
                     Value::Array(Vec::new())
@@ -320,7 +523,7 @@ impl Body {
                 }
             });
 
-        set_value_recursive(&mut current, &parts, 1, &value);
+        set_value_recursive(current, &parts, 1, &value);
     }
 
     // Helper functions
@@ -373,25 +576,132 @@ impl Body {
     }
 }
 
+impl Default for Body {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl From<Vec<u8>> for Body {
     fn from(b: Vec<u8>) -> Body {
         Body::from_bytes(b)
     }
 }
 
+/// Details of a verified TLS client certificate (mutual TLS)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertInfo {
+    /// Subject distinguished name, e.g. `CN=alice, O=Example`
+    pub subject: String,
+    /// Subject common name, if present
+    pub common_name: Option<String>,
+    /// Issuer distinguished name
+    pub issuer: String,
+    /// Lowercase hex SHA-256 of the DER-encoded certificate
+    pub fingerprint: String,
+}
+
+#[cfg(feature = "tls")]
+impl ClientCertInfo {
+    pub(crate) fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+        let common_name = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(str::to_string);
+        let fingerprint = Sha256::digest(der)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Some(Self {
+            subject: cert.subject().to_string(),
+            common_name,
+            issuer: cert.issuer().to_string(),
+            fingerprint,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Request {
     pub method: Method,
     pub path: String,
+    pub(crate) raw_path: String,
     pub query: HashMap<String, String>,
+    pub(crate) raw_query: String,
+    pub(crate) version: String,
+    /// Values captured by `:name` segments of the matched route, decoded
+    /// after matching so `/files/a%2Fb` gives `name` as `a/b`
     pub params: HashMap<String, String>,
     pub headers: HashMap<String, String>,
     pub data: HashMap<String, Value>,
     pub body: Body,
     pub plugins: Plugins,
+    pub client_cert: Option<ClientCertInfo>,
+    /// Address of the connected peer. Behind a proxy this is the proxy;
+    /// the original client is usually in `X-Forwarded-For`.
+    pub remote_addr: Option<SocketAddr>,
+    pub(crate) timing: ServerTiming,
+    pub(crate) deadline: Option<Instant>,
+    // Every header line in arrival order with lowercased names; `headers`
+    // keeps only the last value of repeated headers
+    pub(crate) header_lines: Vec<(String, String)>,
+    // Where `early_hints` queues links for a 103 response; None when the
+    // client can't receive interim responses
+    pub(crate) early_hints: Option<UnboundedSender<Vec<String>>>,
 }
 
 impl Request {
+    /// Builds a request for `target`, a path with an optional query string,
+    /// parsed the same way as a request line from a client. Handy for
+    /// calling handlers and middleware directly, e.g. in tests.
+    ///
+    /// ```rust
+    /// use axeon::{Method, Request};
+    ///
+    /// let req = Request::new(Method::GET, "/search/?q=caf%C3%A9&sort=new")
+    ///     .with_header("Accept", "application/json");
+    /// assert_eq!(req.path, "/search");
+    /// assert_eq!(req.query["q"], "café");
+    /// assert_eq!(req.raw_query(), "q=caf%C3%A9&sort=new");
+    /// assert_eq!(req.get_header("accept"), Some("application/json"));
+    /// ```
+    pub fn new(method: Method, target: &str) -> Self {
+        let (raw_path, raw_query) = target.split_once('?').unwrap_or((target, ""));
+        let path = raw_path.trim_end_matches('/');
+        Self {
+            method,
+            path: if path.is_empty() { "/".to_string() } else { path.to_string() },
+            raw_path: raw_path.to_string(),
+            // Repeated keys keep their last value
+            query: query_pairs(raw_query).collect(),
+            raw_query: raw_query.to_string(),
+            version: "HTTP/1.1".to_string(),
+            params: HashMap::new(),
+            headers: HashMap::new(),
+            data: HashMap::new(),
+            body: Body::new(),
+            plugins: Plugins::default(),
+            client_cert: None,
+            remote_addr: None,
+            timing: ServerTiming::default(),
+            deadline: None,
+            header_lines: Vec::new(),
+            early_hints: None,
+        }
+    }
+
+    /// Adds a header, keeping earlier values for `get_header_all`
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        let name = name.to_lowercase();
+        self.header_lines.push((name.clone(), value.to_string()));
+        self.headers.insert(name, value.to_string());
+        self
+    }
+
     pub fn get_header(&self, key: &str) -> Option<&str> {
         match self.headers.get(key) {
             Some(v) => Some(v),
@@ -399,6 +709,252 @@ impl Request {
         }
     }
 
+    /// Deserializes the query string into `T`. Numbers and booleans are
+    /// parsed from their text, empty values become `None` for `Option`
+    /// fields, and bracketed keys build nested values like form bodies do
+    /// (`tags[]=a&tags[]=b`, `filter[status]=open`). Returns `None` if the
+    /// query doesn't fit `T`.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Pagination {
+    ///     page: u32,
+    ///     limit: u32,
+    ///     tags: Option<Vec<String>>,
+    /// }
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/items", |req| async move {
+    ///     let Some(pagination) = req.query_typed::<Pagination>() else {
+    ///         return Response::text("invalid");
+    ///     };
+    ///     let tags = pagination.tags.unwrap_or_default().join(",");
+    ///     Response::text(format!("page {} of {} tagged {}", pagination.page, pagination.limit, tags))
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn query_typed<T: DeserializeOwned>(&self) -> Option<T> {
+        let mut json = Map::new();
+        for (key, value) in query_pairs(&self.raw_query) {
+            Body::set_nested_value(&mut json, &key, Value::String(value));
+        }
+        T::deserialize(QueryDeserializer(Value::Object(json))).ok()
+    }
+
+    /// Parses the path parameter `name`, failing with a 400 when it is
+    /// missing or doesn't parse as `T`, so handlers can use `?` instead of
+    /// unwrapping.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/users/:id", |req| async move {
+    ///     let id: u64 = req.param("id")?;
+    ///     Response::text(format!("user {}", id))
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn param<T: FromStr>(&self, name: &str) -> Result<T, ServerError> {
+        let value = self
+            .param_str(name)
+            .ok_or_else(|| ServerError::BadRequest(format!("Missing path parameter '{}'", name)))?;
+        value
+            .parse()
+            .map_err(|_| ServerError::BadRequest(format!("Invalid path parameter '{}'", name)))
+    }
+
+    /// Returns the decoded path parameter `name`, if the route has one
+    pub fn param_str(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// The declared body length from the `Content-Length` header, or `None`
+    /// when there is none. Chunked bodies report their decoded length.
+    /// Requests with a malformed or conflicting `Content-Length` never reach
+    /// handlers; the server answers them with 400 and closes the connection.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.post("/upload", |req| async move { Response::text(format!("{:?}", req.content_length())) });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn content_length(&self) -> Option<usize> {
+        self.headers.get("content-length")?.trim().parse().ok()
+    }
+
+    /// Parses a single query parameter, or returns `None` if it is missing
+    /// or doesn't parse as `T`
+    pub fn query_param<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.query.get(key)?.parse().ok()
+    }
+
+    /// Returns the values of every `key` header line in the order received,
+    /// for headers that may be repeated such as `Forwarded`, `Via` or
+    /// `X-Forwarded-For`. `key` is matched case-insensitively.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/", |req| async move {
+    ///     Response::text(req.get_header_all("X-Forwarded-For").join(" | "))
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn get_header_all(&self, key: &str) -> Vec<&str> {
+        self.header_lines
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Returns all cookies from the `Cookie` header. Surrounding double
+    /// quotes are removed from values; pairs without `=` are skipped.
+    pub fn cookies(&self) -> HashMap<String, String> {
+        self.cookie_pairs()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Returns the value of the cookie called `name`
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookie_pairs()
+            .find(|(cookie, _)| *cookie == name)
+            .map(|(_, value)| value)
+    }
+
+    // `session=abc; theme="dark"` yields ("session", "abc"), ("theme", "dark")
+    fn cookie_pairs(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .get("cookie")
+            .map(String::as_str)
+            .unwrap_or("")
+            .split(';')
+            .filter_map(|pair| {
+                let (name, value) = pair.trim().split_once('=')?;
+                let name = name.trim();
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                (!name.is_empty()).then_some((name, value))
+            })
+    }
+
+    /// Records a `Server-Timing` entry for this request
+    pub fn add_timing(&self, name: &str, duration: Duration) {
+        self.timing.add(name, duration);
+    }
+
+    /// Returns a handle to this request's `Server-Timing` collector that
+    /// stays valid after the request is passed on
+    pub fn server_timing(&self) -> ServerTiming {
+        self.timing.clone()
+    }
+
+    /// Sends a `103 Early Hints` interim response with one `Link` header per
+    /// entry, so the client can start preloading while the final response
+    /// is still being produced. Does nothing for HTTP/1.0 clients.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/", |req| async move {
+    ///     req.early_hints(&["</app.css>; rel=preload; as=style"]);
+    ///     Response::html("<link rel=stylesheet href=/app.css>")
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn early_hints(&self, links: &[&str]) {
+        if let Some(hints) = &self.early_hints {
+            let _ = hints.send(links.iter().map(|link| link.to_string()).collect());
+        }
+    }
+
+    /// Returns when this request times out: the server's `request_timeout`
+    /// counted from when the request line arrived, or the handler's own
+    /// timeout if the route sets one with `route_timeout`. Use it to bound
+    /// downstream calls such as database or HTTP client requests.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Method, Response, Server};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let mut app = Server::new();
+    /// app.request_timeout(Duration::from_secs(5));
+    /// app.get("/report", |req| async move {
+    ///     let left = req.deadline().unwrap().saturating_duration_since(Instant::now());
+    ///     Response::text(format!("{}ms left", left.as_millis()))
+    /// });
+    /// app.route_timeout(Method::GET, "/report", Duration::from_secs(60));
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns the path exactly as received, before trailing slashes are
+    /// normalized away and without the query string
+    pub fn raw_path(&self) -> &str {
+        &self.raw_path
+    }
+
+    /// Returns the protocol version from the request line, such as
+    /// `HTTP/1.0`. Requests built with `Request::new` are `HTTP/1.1`.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the query string exactly as received, without the leading
+    /// `?` and without decoding, or an empty string if there was none.
+    pub fn raw_query(&self) -> &str {
+        &self.raw_query
+    }
+
+    /// Returns true for requests sent with `X-Requested-With: XMLHttpRequest`
+    pub fn is_ajax(&self) -> bool {
+        self.headers
+            .get("x-requested-with")
+            .is_some_and(|value| value.eq_ignore_ascii_case("xmlhttprequest"))
+    }
+
+    /// Returns true if the `Accept` header explicitly asks for JSON with at
+    /// least the same preference as HTML. Wildcards alone don't count.
+    pub fn wants_json(&self) -> bool {
+        let Some(accept) = self.headers.get("accept") else {
+            return false;
+        };
+
+        let mut json_q: f32 = 0.0;
+        let mut html_q: f32 = 0.0;
+        for entry in accept.split(',') {
+            let mut parts = entry.split(';');
+            let media_type = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let q = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            if media_type == "application/json"
+                || (media_type.starts_with("application/") && media_type.ends_with("+json"))
+            {
+                json_q = json_q.max(q);
+            } else if media_type == "text/html" {
+                html_q = html_q.max(q);
+            }
+        }
+        json_q > 0.0 && json_q >= html_q
+    }
+
     pub fn get_method(&self) -> &Method {
         &self.method
     }
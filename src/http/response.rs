@@ -1,20 +1,57 @@
 use crate::error::ServerError;
+use crate::http::headers::HeaderMap;
+use crate::http::sse::SseEvent;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
 use serde::Serialize;
 use std::collections::HashMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Body sent with chunked transfer encoding instead of `body`
+pub(crate) struct BodyStream(pub(crate) BoxStream<'static, std::io::Result<Vec<u8>>>);
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BodyStream")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// Attributes for a cookie set with `Response::set_cookie`
+#[derive(Clone, Debug, Default)]
+pub struct CookieOptions {
+    pub path: Option<String>,
+    pub domain: Option<String>,
+    pub max_age: Option<Duration>,
+    pub http_only: bool,
+    pub secure: bool,
+    pub same_site: Option<SameSite>,
+}
 
 #[derive(Debug)]
 pub struct Response {
     pub status: u16,
-    pub body: String,
-    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+    pub headers: HeaderMap,
+    pub(crate) stream: Option<BodyStream>,
 }
 
 impl Response {
     pub fn new(status: u16) -> Response {
         Response {
             status,
-            headers: HashMap::new(),
-            body: String::new(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            stream: None,
         }
     }
 
@@ -26,13 +63,133 @@ impl Response {
 
     // Generic body setter
     pub fn body<T: AsRef<str>>(&mut self, body: T) -> &mut Self {
-        self.body = body.as_ref().to_string();
+        self.body = body.as_ref().as_bytes().to_vec();
+        self
+    }
+
+    /// Sets a raw byte body, for content that is not UTF-8 text.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::new(200);
+    /// response.body_bytes(vec![0x89, b'P', b'N', b'G']);
+    /// assert_eq!(response.body, [0x89, b'P', b'N', b'G']);
+    /// ```
+    pub fn body_bytes(&mut self, body: Vec<u8>) -> &mut Self {
+        self.body = body;
         self
     }
 
-    // Generic header setter
+    /// Size of the body in bytes, or `None` for a streamed body whose size
+    /// is only known once it has been sent. Middleware that inspects
+    /// response sizes should treat `None` as unknown rather than empty.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// assert_eq!(Response::text("hello").unwrap().body_len(), Some(5));
+    /// let csv: &'static [u8] = b"id,name\n";
+    /// assert_eq!(Response::from_reader(csv, "text/csv").unwrap().body_len(), None);
+    /// ```
+    pub fn body_len(&self) -> Option<usize> {
+        if self.stream.is_some() {
+            None
+        } else {
+            Some(self.body.len())
+        }
+    }
+
+    /// Sets a header, replacing any existing value. Same as `set_header`.
     pub fn header<K: AsRef<str>, V: AsRef<str>>(&mut self, name: K, value: V) -> &mut Self {
-        self.headers.insert(name.as_ref().to_string(), value.as_ref().to_string());
+        self.set_header(name, value)
+    }
+
+    /// Sets a header, replacing any existing value regardless of the case
+    /// its name was set with.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::text("{}").unwrap();
+    /// response.set_header("content-type", "application/json");
+    /// assert_eq!(response.headers.len(), 1);
+    /// assert_eq!(response.headers["content-type"], "application/json");
+    /// ```
+    pub fn set_header<K: AsRef<str>, V: AsRef<str>>(&mut self, name: K, value: V) -> &mut Self {
+        self.headers.insert(name.as_ref(), value.as_ref());
+        self
+    }
+
+    /// Adds a header value without overwriting an existing one. Each value
+    /// is sent as its own header line, so this also works for `Set-Cookie`,
+    /// which can't be combined into a comma-separated list.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::new(200);
+    /// response
+    ///     .append_header("Link", "</page/2>; rel=\"next\"")
+    ///     .append_header("Link", "</page/9>; rel=\"last\"")
+    ///     .append_header("Set-Cookie", "a=1")
+    ///     .append_header("Set-Cookie", "b=2");
+    /// assert_eq!(response.get_header_all("link"), ["</page/2>; rel=\"next\"", "</page/9>; rel=\"last\""]);
+    /// assert_eq!(response.set_cookies(), ["a=1", "b=2"]);
+    /// ```
+    pub fn append_header<K: AsRef<str>, V: AsRef<str>>(&mut self, name: K, value: V) -> &mut Self {
+        self.headers.append(name.as_ref(), value.as_ref());
+        self
+    }
+
+    /// Returns the first value of a header, whatever the case of its name.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::new(200);
+    /// response.set_header("Set-Cookie", "session=abc123");
+    /// assert_eq!(response.get_header("set-cookie"), Some("session=abc123"));
+    /// assert_eq!(response.get_header("X-Missing"), None);
+    /// ```
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    /// Returns every value of a header, in the order they were added
+    pub fn get_header_all(&self, name: &str) -> Vec<&str> {
+        self.headers.get_all(name)
+    }
+
+    /// Copies every header and cookie from `other`, replacing headers of the
+    /// same name. Status and body are left untouched, so a middleware can
+    /// build headers on a separate response and apply them afterwards.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut extra = Response::new(200);
+    /// extra.header("Cache-Control", "no-store").header("X-Request-Id", "42");
+    ///
+    /// let mut response = Response::text("created").unwrap();
+    /// response.status(201).header("cache-control", "max-age=60");
+    /// response.merge_headers_from(&extra);
+    ///
+    /// assert_eq!(response.status, 201);
+    /// assert_eq!(response.body, b"created");
+    /// assert_eq!(response.headers["Cache-Control"], "no-store");
+    /// assert_eq!(response.headers["X-Request-Id"], "42");
+    /// assert_eq!(response.get_header_all("cache-control"), ["no-store"]);
+    /// ```
+    pub fn merge_headers_from(&mut self, other: &Response) -> &mut Self {
+        for name in other.headers.keys() {
+            if !name.eq_ignore_ascii_case("set-cookie") {
+                self.headers.remove(name);
+            }
+        }
+        for (name, value) in &other.headers {
+            self.headers.append(name.as_str(), value.as_str());
+        }
         self
     }
 
@@ -70,6 +227,43 @@ impl Response {
         Ok(response)
     }
 
+    /// Builds a JSON response with any status, for statuses without their
+    /// own constructor.
+    ///
+    /// ```rust
+    /// use axeon::{json, Response};
+    ///
+    /// let results = json!([{ "id": 1, "status": 200 }, { "id": 2, "status": 404 }]);
+    /// let response = Response::json_with_status(207, &results).unwrap();
+    /// assert_eq!(response.status, 207);
+    /// assert_eq!(response.headers["Content-Type"], "application/json");
+    /// let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    /// assert_eq!(body, results);
+    /// ```
+    pub fn json_with_status<T: Serialize>(status: u16, data: &T) -> Result<Response, ServerError> {
+        let mut response = Response::new(status);
+        response.json(data)?;
+        Ok(response)
+    }
+
+    /// Builds a 200 JSON response from already serialized bytes, without
+    /// re-encoding them.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let response = Response::json_raw(br#"{"id":1}"#.to_vec()).unwrap();
+    /// assert_eq!(response.body, br#"{"id":1}"#);
+    /// assert_eq!(response.headers["Content-Type"], "application/json");
+    /// ```
+    pub fn json_raw(bytes: Vec<u8>) -> Result<Response, ServerError> {
+        let mut response = Response::new(200);
+        response
+            .header("Content-Type", "application/json")
+            .body_bytes(bytes);
+        Ok(response)
+    }
+
     pub fn no_content() -> Response {
         Response::new(204)
     }
@@ -103,6 +297,7 @@ impl Response {
         let status = err.status_code();
         let error_message = err.to_string();
         let mut response = Response::new(status);
+        response.headers(err.headers().into_iter().collect());
         response.json(&serde_json::json!({
             "error": {
                 "message": error_message,
@@ -130,7 +325,7 @@ impl Response {
         for (name, value) in &self.headers {
             println!("{}: {}", name, value);
         }
-        println!("\r\n{}", self.body);
+        println!("\r\n{}", String::from_utf8_lossy(&self.body));
     }
 
     // New convenience methods
@@ -158,6 +353,175 @@ impl Response {
         Ok(response)
     }
 
+    /// Reads a file asynchronously and builds a 200 response with the
+    /// Content-Type derived from its extension.
+    ///
+    /// Returns `ServerError::NotFound` if the file does not exist.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let dir = std::env::temp_dir().join("axeon-mime-doctest");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let runtime = tokio::runtime::Runtime::new().unwrap();
+    /// for (file, expected) in [
+    ///     ("data.json", "application/json"),
+    ///     ("app.wasm", "application/wasm"),
+    ///     ("font.woff2", "font/woff2"),
+    ///     ("clip.mp4", "video/mp4"),
+    ///     ("PHOTO.WEBP", "image/webp"),
+    /// ] {
+    ///     std::fs::write(dir.join(file), b"").unwrap();
+    ///     let response = runtime.block_on(Response::from_file(dir.join(file))).unwrap();
+    ///     assert_eq!(response.headers["Content-Type"], expected);
+    /// }
+    /// ```
+    pub async fn from_file<P: AsRef<Path>>(path: P) -> Result<Response, ServerError> {
+        let path = path.as_ref();
+        let contents = tokio::fs::read(path).await.map_err(|e| match e.kind() {
+            ErrorKind::NotFound => ServerError::NotFound,
+            _ => ServerError::IoError(e),
+        })?;
+        let mut response = Response::new(200);
+        response
+            .header("Content-Type", content_type_for(path))
+            .body_bytes(contents);
+        Ok(response)
+    }
+
+    /// Builds a 200 response whose body is streamed from `reader` to the
+    /// client with chunked transfer encoding, without buffering it fully.
+    ///
+    /// ```rust
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/export", |_req| async {
+    ///     let csv: &'static [u8] = b"id,name\n1,alice\n";
+    ///     Response::from_reader(csv, "text/csv")
+    /// });
+    /// ```
+    pub fn from_reader<R>(reader: R, content_type: &str) -> Result<Response, ServerError>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        // Reads fixed-size chunks until EOF, stopping after the first error
+        let chunks = stream::unfold(Some(reader), |reader| async move {
+            let mut reader = reader?;
+            let mut buffer = vec![0u8; 8192];
+            match reader.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(read) => {
+                    buffer.truncate(read);
+                    Some((Ok(buffer), Some(reader)))
+                }
+                Err(err) => Some((Err(err), None)),
+            }
+        });
+
+        let mut response = Response::new(200);
+        response.header("Content-Type", content_type);
+        response.stream = Some(BodyStream(chunks.boxed()));
+        Ok(response)
+    }
+
+    /// Sends the chunks yielded by `chunks` as the body, with chunked
+    /// transfer encoding. The next chunk is only polled once the previous
+    /// one has been written and flushed, so a slow client slows the
+    /// producer down instead of chunks piling up in memory. An error ends
+    /// the response early and closes the connection.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server};
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/rows", |_req| async {
+    ///     let rows = ["id,name\n", "1,alice\n", "2,bob\n"];
+    ///     let chunks = futures::stream::iter(rows.map(|row| Ok(row.as_bytes().to_vec())));
+    ///     Response::from_stream(chunks, "text/csv")
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn from_stream<S>(chunks: S, content_type: &str) -> Result<Response, ServerError>
+    where
+        S: Stream<Item = std::io::Result<Vec<u8>>> + Send + 'static,
+    {
+        let mut response = Response::new(200);
+        response.header("Content-Type", content_type);
+        response.stream = Some(BodyStream(chunks.boxed()));
+        Ok(response)
+    }
+
+    /// Streams `events` to the client as Server-Sent Events. Each event is
+    /// written and flushed as soon as the stream yields it; the response
+    /// ends when the stream does.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server, SseEvent};
+    /// use std::time::Duration;
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/ticks", |_req| async {
+    ///     let ticks = futures::stream::unfold(1, |n| async move {
+    ///         tokio::time::sleep(Duration::from_secs(1)).await;
+    ///         Some((SseEvent::new(format!("tick {}", n)).event("tick").id(n.to_string()), n + 1))
+    ///     });
+    ///     Response::sse(ticks)
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn sse<S>(events: S) -> Result<Response, ServerError>
+    where
+        S: Stream<Item = SseEvent> + Send + 'static,
+    {
+        let frames = events.map(|event| Ok(event.to_frame().into_bytes()));
+        Ok(Self::event_stream(frames.boxed()))
+    }
+
+    /// Like `sse`, but starts with a `retry:` line setting the client's
+    /// reconnection delay, and sends a `:keep-alive` comment whenever no
+    /// event has been sent for `keep_alive`, so proxies don't close the
+    /// idle connection.
+    ///
+    /// ```rust,no_run
+    /// use axeon::{Response, Server, SseEvent};
+    /// use std::time::Duration;
+    ///
+    /// let mut app = Server::new();
+    /// app.get("/jobs", |_req| async {
+    ///     let done = futures::stream::once(async {
+    ///         tokio::time::sleep(Duration::from_secs(60)).await;
+    ///         SseEvent::new("done")
+    ///     });
+    ///     Response::sse_with_retry(done, Duration::from_secs(2), Duration::from_secs(15))
+    /// });
+    /// app.listen("127.0.0.1:3000").unwrap();
+    /// ```
+    pub fn sse_with_retry<S>(events: S, retry: Duration, keep_alive: Duration) -> Result<Response, ServerError>
+    where
+        S: Stream<Item = SseEvent> + Send + 'static,
+    {
+        let first = stream::once(async move { Ok(format!("retry: {}\n\n", retry.as_millis()).into_bytes()) });
+        // Waits for the next event, sending a comment each time `keep_alive`
+        // passes without one
+        let frames = stream::unfold(events.boxed(), move |mut events| async move {
+            tokio::select! {
+                event = events.next() => event.map(|event| (Ok(event.to_frame().into_bytes()), events)),
+                _ = tokio::time::sleep(keep_alive) => Some((Ok(b":keep-alive\n\n".to_vec()), events)),
+            }
+        });
+        Ok(Self::event_stream(first.chain(frames).boxed()))
+    }
+
+    fn event_stream(frames: BoxStream<'static, std::io::Result<Vec<u8>>>) -> Response {
+        let mut response = Response::new(200);
+        response
+            .header("Content-Type", "text/event-stream")
+            .header("Cache-Control", "no-cache");
+        response.stream = Some(BodyStream(frames));
+        response
+    }
+
     pub fn redirect(location: &str) -> Result<Response, ServerError> {
         let mut response = Response::new(302);
         response.header("Location", location);
@@ -170,6 +534,41 @@ impl Response {
         Ok(response)
     }
 
+    /// Sets a cookie and redirects with 303 See Other, so the browser
+    /// follows up with a GET, e.g. after a login form is posted.
+    ///
+    /// ```rust
+    /// use axeon::{CookieOptions, Response};
+    ///
+    /// let options = CookieOptions { http_only: true, ..CookieOptions::default() };
+    /// let response = Response::redirect_with_cookie("/dashboard", "session", "abc123", options).unwrap();
+    /// assert_eq!(response.status, 303);
+    /// assert_eq!(response.headers["Location"], "/dashboard");
+    /// assert_eq!(response.set_cookies(), ["session=abc123; HttpOnly"]);
+    /// ```
+    pub fn redirect_with_cookie(
+        location: &str,
+        name: &str,
+        value: &str,
+        options: CookieOptions,
+    ) -> Result<Response, ServerError> {
+        let mut response = Response::new(303);
+        response
+            .header("Location", location)
+            .set_cookie(name, value, options);
+        Ok(response)
+    }
+
+    /// Builds a 405 response with an `Allow` header. The server sends one
+    /// automatically when a path is routed but not for the request's method.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let response = Response::method_not_allowed(&["GET", "HEAD", "OPTIONS"]).unwrap();
+    /// assert_eq!(response.status, 405);
+    /// assert_eq!(response.headers["Allow"], "GET, HEAD, OPTIONS");
+    /// ```
     pub fn method_not_allowed(allowed_methods: &[&str]) -> Result<Response, ServerError> {
         let mut response = Response::new(405);
         response
@@ -183,6 +582,63 @@ impl Response {
         Ok(response)
     }
 
+    /// Adds `Link` headers (`first`, `prev`, `next`, `last`) and an
+    /// `X-Total-Count` header for a 1-based `page` of `per_page` items out
+    /// of `total`. Page links are built by adding `page` and `per_page`
+    /// query parameters to `base_url`.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::new(200);
+    /// response.paginate("/users", 3, 10, 95);
+    /// let links = response.get_header_all("Link");
+    /// assert_eq!(links.len(), 4);
+    /// assert_eq!(links[1], "</users?page=2&per_page=10>; rel=\"prev\"");
+    /// assert_eq!(links[2], "</users?page=4&per_page=10>; rel=\"next\"");
+    /// assert_eq!(links[3], "</users?page=10&per_page=10>; rel=\"last\"");
+    /// ```
+    pub fn paginate(&mut self, base_url: &str, page: u64, per_page: u64, total: u64) -> &mut Self {
+        let per_page = per_page.max(1);
+        let last = total.div_ceil(per_page).max(1);
+        let separator = if base_url.contains('?') { '&' } else { '?' };
+        let link = |page: u64, rel: &str| {
+            format!("<{}{}page={}&per_page={}>; rel=\"{}\"", base_url, separator, page, per_page, rel)
+        };
+
+        self.append_header("Link", link(1, "first"));
+        if page > 1 {
+            self.append_header("Link", link((page - 1).min(last), "prev"));
+        }
+        if page < last {
+            self.append_header("Link", link(page + 1, "next"));
+        }
+        self.append_header("Link", link(last, "last"))
+            .header("X-Total-Count", total.to_string())
+    }
+
+    /// Builds a 200 JSON response wrapping `items` in a
+    /// `{ data, page, per_page, total }` envelope, with pagination headers
+    /// as set by `paginate`.
+    pub fn paginated<T: Serialize>(
+        items: &T,
+        base_url: &str,
+        page: u64,
+        per_page: u64,
+        total: u64,
+    ) -> Result<Response, ServerError> {
+        let mut response = Response::new(200);
+        response
+            .json(&serde_json::json!({
+                "data": items,
+                "page": page,
+                "per_page": per_page,
+                "total": total,
+            }))?
+            .paginate(base_url, page, per_page, total);
+        Ok(response)
+    }
+
     pub fn with_cache_control(&mut self, directive: &str) -> &mut Self {
         self.header("Cache-Control", directive)
     }
@@ -193,6 +649,48 @@ impl Response {
             .header("Expires", "0")
     }
 
+    /// Allows only the client's own cache to store the response, not shared
+    /// caches such as CDNs or proxies
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::text("account").unwrap();
+    /// response.private();
+    /// assert_eq!(response.headers["Cache-Control"], "private");
+    /// ```
+    pub fn private(&mut self) -> &mut Self {
+        self.with_cache_control("private")
+    }
+
+    /// Forbids any cache from storing the response, e.g. for sensitive data
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::text("secret").unwrap();
+    /// response.private().no_store();
+    /// assert_eq!(response.headers["Cache-Control"], "no-store");
+    /// ```
+    pub fn no_store(&mut self) -> &mut Self {
+        self.with_cache_control("no-store")
+    }
+
+    /// Lets every cache keep the response for `max_age` without
+    /// revalidating, for fingerprinted assets whose content never changes
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    /// use std::time::Duration;
+    ///
+    /// let mut response = Response::text("body{}").unwrap();
+    /// response.immutable(Duration::from_secs(31_536_000));
+    /// assert_eq!(response.headers["Cache-Control"], "public, max-age=31536000, immutable");
+    /// ```
+    pub fn immutable(&mut self, max_age: Duration) -> &mut Self {
+        self.with_cache_control(&format!("public, max-age={}, immutable", max_age.as_secs()))
+    }
+
     // Security headers
     pub fn with_security_headers(&mut self) -> &mut Self {
         self.header("X-Content-Type-Options", "nosniff")
@@ -226,9 +724,99 @@ impl Response {
         Ok(response)
     }
 
+    /// Adds a `Set-Cookie` header. Unlike other headers, every call adds
+    /// another one, so several cookies can be set on the same response.
+    ///
+    /// ```rust
+    /// use axeon::{CookieOptions, Response, SameSite};
+    /// use std::time::Duration;
+    ///
+    /// let mut response = Response::text("logged in").unwrap();
+    /// response
+    ///     .set_cookie("session", "abc123", CookieOptions {
+    ///         path: Some("/".to_string()),
+    ///         max_age: Some(Duration::from_secs(3600)),
+    ///         http_only: true,
+    ///         secure: true,
+    ///         same_site: Some(SameSite::Lax),
+    ///         ..CookieOptions::default()
+    ///     })
+    ///     .set_cookie("theme", "dark", CookieOptions::default());
+    ///
+    /// assert_eq!(
+    ///     response.set_cookies(),
+    ///     ["session=abc123; Path=/; Max-Age=3600; HttpOnly; Secure; SameSite=Lax", "theme=dark"]
+    /// );
+    /// ```
+    pub fn set_cookie(&mut self, name: &str, value: &str, options: CookieOptions) -> &mut Self {
+        let mut cookie = format!("{}={}", name, value);
+        if let Some(path) = &options.path {
+            cookie += &format!("; Path={}", path);
+        }
+        if let Some(domain) = &options.domain {
+            cookie += &format!("; Domain={}", domain);
+        }
+        if let Some(max_age) = options.max_age {
+            cookie += &format!("; Max-Age={}", max_age.as_secs());
+        }
+        if options.http_only {
+            cookie += "; HttpOnly";
+        }
+        if options.secure {
+            cookie += "; Secure";
+        }
+        if let Some(same_site) = options.same_site {
+            cookie += &format!("; SameSite={:?}", same_site);
+        }
+        self.headers.append("Set-Cookie", cookie);
+        self
+    }
+
+    /// Returns the `Set-Cookie` header values, one per cookie
+    pub fn set_cookies(&self) -> Vec<&str> {
+        self.headers.get_all("Set-Cookie")
+    }
+
     pub fn file_download(&mut self, filename: &str, content_type: &str) -> &mut Self {
         self.header("Content-Type", content_type)
-            .header("Content-Disposition", &format!("attachment; filename=\"{}\"", filename))
+            .header("Content-Disposition", format!("attachment; filename=\"{}\"", filename))
+    }
+
+    /// Sets the ETag header, quoting `value` unless it is already a quoted or
+    /// weak entity tag. GET and HEAD requests whose `If-None-Match` matches it
+    /// get a 304 Not Modified instead of the body.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    ///
+    /// let mut response = Response::text("report").unwrap();
+    /// response.etag("v42");
+    /// assert_eq!(response.headers["ETag"], "\"v42\"");
+    /// ```
+    pub fn etag(&mut self, value: &str) -> &mut Self {
+        if value.starts_with('"') || value.starts_with("W/") {
+            self.header("ETag", value)
+        } else {
+            self.header("ETag", format!("\"{}\"", value))
+        }
+    }
+
+    /// Sets the Last-Modified header. GET and HEAD requests whose
+    /// `If-Modified-Since` is at or after it get a 304 Not Modified instead
+    /// of the body, unless they also send `If-None-Match`, which takes
+    /// precedence.
+    ///
+    /// ```rust
+    /// use axeon::Response;
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// let published = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    /// let mut response = Response::text("article").unwrap();
+    /// response.last_modified(published);
+    /// assert_eq!(response.headers["Last-Modified"], "Tue, 14 Nov 2023 22:13:20 GMT");
+    /// ```
+    pub fn last_modified(&mut self, modified: SystemTime) -> &mut Self {
+        self.header("Last-Modified", httpdate::fmt_http_date(modified))
     }
 
     pub fn vary(&mut self, headers: &[&str]) -> &mut Self {
@@ -255,6 +843,65 @@ impl Response {
     }
 }
 
+// Content type lookup based on file extension, case-insensitive. Unknown
+// extensions are served as `application/octet-stream`.
+pub(crate) fn content_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        // Text and documents
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "json" | "map" => "application/json",
+        "jsonld" => "application/ld+json",
+        "webmanifest" => "application/manifest+json",
+        "xml" => "application/xml",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "ics" => "text/calendar",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        // Images
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        // Fonts
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        // Audio and video
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "flac" => "audio/flac",
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "ogv" => "video/ogg",
+        "mov" => "video/quicktime",
+        "vtt" => "text/vtt",
+        // Archives
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "7z" => "application/x-7z-compressed",
+        _ => "application/octet-stream",
+    }
+}
+
 #[macro_export]
 macro_rules! ok_json {
     ($($json:tt)+) => {{
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+/// One Server-Sent Event, sent by `Response::sse`.
+///
+/// Multi-line `data` is split into one `data:` line per line, which the
+/// client joins back with newlines.
+///
+/// ```rust
+/// use axeon::SseEvent;
+/// use std::time::Duration;
+///
+/// let event = SseEvent::new("line one\nline two")
+///     .event("update")
+///     .id("7")
+///     .retry(Duration::from_secs(3));
+/// assert_eq!(
+///     event.to_frame(),
+///     "event: update\nid: 7\nretry: 3000\ndata: line one\ndata: line two\n\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+    /// How long the client should wait before reconnecting
+    pub retry: Option<Duration>,
+}
+
+impl SseEvent {
+    pub fn new<T: Into<String>>(data: T) -> Self {
+        Self {
+            data: data.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the event name, dispatched to `addEventListener(name)` listeners
+    pub fn event<T: Into<String>>(mut self, event: T) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Sets the event id, sent back by the client as `Last-Event-ID`
+    pub fn id<T: Into<String>>(mut self, id: T) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Renders the event in the `text/event-stream` wire format
+    pub fn to_frame(&self) -> String {
+        let mut frame = String::new();
+        // Newlines would end the field early, so they are dropped from the
+        // single-line fields
+        if let Some(event) = &self.event {
+            frame += &format!("event: {}\n", event.replace(['\r', '\n'], ""));
+        }
+        if let Some(id) = &self.id {
+            frame += &format!("id: {}\n", id.replace(['\r', '\n'], ""));
+        }
+        if let Some(retry) = self.retry {
+            frame += &format!("retry: {}\n", retry.as_millis());
+        }
+        for line in self.data.split('\n') {
+            frame += &format!("data: {}\n", line.trim_end_matches('\r'));
+        }
+        frame.push('\n');
+        frame
+    }
+}
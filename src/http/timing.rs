@@ -0,0 +1,51 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Collects `Server-Timing` entries while a request is processed.
+///
+/// Every clone shares the same entries, so middleware can keep a handle
+/// from `Request::server_timing` and still see what the handler added after
+/// the request was moved down the chain. The server emits the combined
+/// header on the response once any entry was recorded.
+///
+/// ```rust
+/// use axeon::ServerTiming;
+/// use std::time::Duration;
+///
+/// let timing = ServerTiming::default();
+/// let handler_view = timing.clone();
+///
+/// timing.add("auth", Duration::from_micros(1500));
+/// handler_view.add("db", Duration::from_millis(12));
+///
+/// assert_eq!(timing.header_value().unwrap(), "auth;dur=1.500, db;dur=12.000");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ServerTiming {
+    entries: Arc<Mutex<Vec<(String, Duration)>>>,
+}
+
+impl ServerTiming {
+    /// Records a named duration
+    pub fn add(&self, name: &str, duration: Duration) {
+        self.entries
+            .lock()
+            .unwrap()
+            .push((name.to_string(), duration));
+    }
+
+    /// Formats the recorded entries as a `Server-Timing` header value, with
+    /// durations in milliseconds. Returns `None` if nothing was recorded.
+    pub fn header_value(&self) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        if entries.is_empty() {
+            return None;
+        }
+        let value = entries
+            .iter()
+            .map(|(name, duration)| format!("{};dur={:.3}", name, duration.as_secs_f64() * 1000.0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Some(value)
+    }
+}
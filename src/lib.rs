@@ -11,9 +11,16 @@
 //! - JSON request/response handling with type safety
 //! - Async/await support throughout the framework
 //!
+//! ## Cargo Features
+//!
+//! - `cache` (default): the `cache` module, backed by `moka`
+//! - `compression` (default): `middleware::CompressionMiddleware`, backed by `flate2`
+//! - `database` (default): the `database` module and its connection pool
+//! - `tls` (default): `Server::with_tls` and `Server::with_client_auth`, backed by `rustls`
+//!
 //! ## Quick Start
 //!
-//! ```rust
+//! ```rust,no_run
 //! use axeon::{Response, Server};
 //!
 //! fn main() {
@@ -59,6 +66,7 @@
 //!     role: String,
 //! }
 //!
+//! let mut app = Server::new();
 //! app.post("/users", |req| async move {
 //!     match req.body.json::<User>() {
 //!         Some(user) => Response::ok(&user),
@@ -80,7 +88,7 @@
 //!     fn call(&self, req: Request, next: Next) -> MiddlewareResult {
 //!         Box::pin(async move {
 //!             let start = Instant::now();
-//!             let method = req.method.clone();
+//!             let method = req.method;
 //!             let url = req.path.clone();
 //!             
 //!             let res = next.handle(req).await;
@@ -123,7 +131,10 @@
 pub extern crate serde_json;
 pub(crate) mod app;
 pub mod buffer;
+#[cfg(feature = "cache")]
 pub mod cache;
+pub(crate) mod config;
+#[cfg(feature = "database")]
 pub mod database;
 pub(crate) mod error;
 pub(crate) mod handler;
@@ -133,11 +144,17 @@ pub(crate) mod plugins;
 pub(crate) mod router;
 
 pub use app::Server;
-pub use router::Router;
+pub use config::ServerSettings;
+pub use router::{route, RouteDef, Router, TrailingSlashMode};
 
-pub use crate::error::ServerError;
-pub use crate::http::request::{Body, Method, ParseError, Request};
-pub use crate::http::response::Response;
+pub use crate::error::{ErrorInfo, ServerError, SetupError};
+pub use crate::handler::{json_handler, Handler};
+pub use crate::http::headers::HeaderMap;
+pub use crate::http::json::{Json, JsonLimits};
+pub use crate::http::request::{Body, ClientCertInfo, Method, ParseError, Request};
+pub use crate::http::response::{CookieOptions, Response, SameSite};
+pub use crate::http::sse::SseEvent;
+pub use crate::http::timing::ServerTiming;
 
 // Reexport serde_json
 pub use serde_json::{json, Value};
@@ -0,0 +1,208 @@
+use crate::http::Request;
+use crate::middleware::{client_ip, Middleware, MiddlewareResult, Next};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Apache combined log format, followed by the latency in milliseconds
+    Combined,
+    /// One JSON object per line
+    Json,
+}
+
+/// Middleware that writes one line per request to a configurable sink.
+///
+/// The client IP is the peer address of the connection; behind a reverse
+/// proxy, list the proxy with `trust_proxies` to log the `X-Forwarded-For`
+/// address it adds instead.
+///
+/// # Example
+///
+/// ```rust
+/// use axeon::Server;
+/// use axeon::middleware::{AccessLog, LogFormat};
+///
+/// let mut app = Server::new();
+/// app.middleware(AccessLog::new(LogFormat::Json).with_sink(|line| eprintln!("{}", line)));
+/// ```
+#[derive(Clone)]
+pub struct AccessLog {
+    format: LogFormat,
+    sink: LogSink,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl AccessLog {
+    /// Creates an access log writing to stdout
+    pub fn new(format: LogFormat) -> Self {
+        Self {
+            format,
+            sink: Arc::new(|line| println!("{}", line)),
+            trusted_proxies: Vec::new(),
+        }
+    }
+
+    /// Sends each log line to `sink` instead of stdout
+    pub fn with_sink<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.sink = Arc::new(sink);
+        self
+    }
+
+    /// Logs the `X-Forwarded-For` client when the request comes from one of
+    /// `proxies`. Only list proxies that overwrite or append to it.
+    pub fn trust_proxies(mut self, proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.trusted_proxies = proxies.into_iter().collect();
+        self
+    }
+}
+
+struct Entry {
+    timestamp: SystemTime,
+    method: String,
+    path: String,
+    version: String,
+    status: u16,
+    /// Unknown for streamed bodies and errors, logged as `-`
+    bytes: Option<usize>,
+    latency_ms: f64,
+    client_ip: String,
+    user_agent: String,
+    referer: String,
+}
+
+impl Entry {
+    fn format(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Combined => format!(
+                "{} - - [{}] \"{} {} {}\" {} {} \"{}\" \"{}\" {:.3}",
+                self.client_ip,
+                clf_timestamp(self.timestamp),
+                self.method,
+                escape_quoted(&self.path),
+                self.version,
+                self.status,
+                self.bytes.map_or_else(|| "-".to_string(), |bytes| bytes.to_string()),
+                escape_quoted(&self.referer),
+                escape_quoted(&self.user_agent),
+                self.latency_ms,
+            ),
+            LogFormat::Json => serde_json::json!({
+                "timestamp": httpdate::fmt_http_date(self.timestamp),
+                "method": self.method,
+                "path": self.path,
+                "status": self.status,
+                "bytes": self.bytes,
+                "latency_ms": self.latency_ms,
+                "client_ip": self.client_ip,
+                "user_agent": self.user_agent,
+            })
+            .to_string(),
+        }
+    }
+}
+
+// Escapes a value for a quoted Combined field, so it can't end the field
+// or the line early: `"` and `\` are backslash-escaped and control
+// characters are written as `\xhh`, as Apache does
+fn escape_quoted(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped += &format!("\\x{:02x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Formats a timestamp as `10/Oct/2000:13:55:36 +0000`
+fn clf_timestamp(time: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86400, secs % 86400);
+
+    // Civil date from days since epoch (Howard Hinnant's algorithm)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+impl Middleware for AccessLog {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        let format = self.format;
+        let sink = Arc::clone(&self.sink);
+        let trusted_proxies = self.trusted_proxies.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            let timestamp = SystemTime::now();
+            let header = |name: &str| req.headers.get(name).cloned().unwrap_or_else(|| "-".to_string());
+            let client_ip = client_ip(&req, &trusted_proxies).unwrap_or_else(|| "-".to_string());
+            let user_agent = header("user-agent");
+            let referer = header("referer");
+            let method = format!("{:?}", req.method);
+            // The target as the client sent it, before rewrites and slash trimming
+            let path = match req.raw_query() {
+                "" => req.raw_path().to_string(),
+                query => format!("{}?{}", req.raw_path(), query),
+            };
+            let version = req.version().to_string();
+
+            let res = next.handle(req).await;
+            let (status, bytes) = match &res {
+                // Streamed bodies are sent after this returns, and errors are
+                // only turned into a response by the server
+                Ok(response) if response.stream.is_some() => (response.status, None),
+                Ok(response) => (response.status, Some(response.body.len())),
+                Err(err) => (err.status_code(), None),
+            };
+
+            let entry = Entry {
+                timestamp,
+                method,
+                path,
+                version,
+                status,
+                bytes,
+                latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+                client_ip,
+                user_agent,
+                referer,
+            };
+            sink(&entry.format(format));
+            res
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::ServerError;
+use crate::http::{Request, Response};
+use crate::middleware::{Middleware, MiddlewareResult, Next};
+use base64::Engine;
+use std::sync::Arc;
+
+type Verifier = Arc<dyn Fn(&str, &str) -> bool + Send + Sync>;
+
+/// Middleware that checks `Authorization: Basic` credentials.
+///
+/// Requests with missing or rejected credentials get a 401 with a
+/// `WWW-Authenticate: Basic` challenge. On success the username is stored
+/// in the request data under `"user"`.
+///
+/// # Example
+///
+/// ```rust
+/// use axeon::Server;
+/// use axeon::middleware::{constant_time_eq, BasicAuthMiddleware};
+///
+/// let mut app = Server::new();
+/// app.middleware(
+///     BasicAuthMiddleware::new(|user, password| {
+///         constant_time_eq(user, "admin") & constant_time_eq(password, "secret")
+///     })
+///     .realm("admin"),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct BasicAuthMiddleware {
+    verify: Verifier,
+    realm: String,
+}
+
+impl BasicAuthMiddleware {
+    pub fn new<F>(verify: F) -> Self
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            verify: Arc::new(verify),
+            realm: "Restricted".to_string(),
+        }
+    }
+
+    /// Accepts only `user` with `password`, compared in constant time
+    pub fn single_user(user: &str, password: &str) -> Self {
+        let (user, password) = (user.to_string(), password.to_string());
+        Self::new(move |given_user, given_password| {
+            constant_time_eq(given_user, &user) & constant_time_eq(given_password, &password)
+        })
+    }
+
+    /// Sets the realm sent in the `WWW-Authenticate` challenge
+    pub fn realm(mut self, realm: &str) -> Self {
+        self.realm = realm.to_string();
+        self
+    }
+
+    fn parse_credentials(header: &str) -> Option<(String, String)> {
+        let (scheme, encoded) = header.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("basic") {
+            return None;
+        }
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (user, password) = decoded.split_once(':')?;
+        Some((user.to_string(), password.to_string()))
+    }
+
+    fn challenge(&self, message: &str) -> Response {
+        Response::error(ServerError::Unauthorized {
+            message: message.to_string(),
+            challenge: format!("Basic realm=\"{}\", charset=\"UTF-8\"", self.realm),
+        })
+    }
+}
+
+/// Compares two strings in time that depends only on their lengths, so
+/// verifiers don't reveal how much of a secret a guess got right.
+///
+/// ```rust
+/// use axeon::middleware::constant_time_eq;
+///
+/// assert!(constant_time_eq("secret", "secret"));
+/// assert!(!constant_time_eq("secret", "secreT"));
+/// assert!(!constant_time_eq("secret", "secrets"));
+/// ```
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= usize::from(x ^ y);
+    }
+    diff == 0
+}
+
+impl Middleware for BasicAuthMiddleware {
+    fn call(&self, mut req: Request, next: Next) -> MiddlewareResult {
+        let this = self.clone();
+        Box::pin(async move {
+            let credentials = req
+                .headers
+                .get("authorization")
+                .and_then(|header| Self::parse_credentials(header));
+
+            match credentials {
+                Some((user, password)) if (this.verify)(&user, &password) => {
+                    req.set_data("user", user);
+                    next.handle(req).await
+                }
+                Some(_) => Ok(this.challenge("Invalid credentials")),
+                None => Ok(this.challenge("Missing credentials")),
+            }
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
@@ -1,14 +1,36 @@
-use crate::http::Request;
+use crate::http::response::BodyStream;
+use crate::http::{Request, Response};
 use crate::middleware::{Middleware, MiddlewareResult, Next};
 use flate2::write::{GzEncoder, DeflateEncoder};
 use flate2::Compression;
+use futures::stream::{self, StreamExt};
 use std::io::Write;
 
+/// Settings for `CompressionMiddleware`.
+///
+/// Content types are matched by prefix, so `"text/"` covers every text
+/// type. By default everything except `skip_types` is compressed. Setting
+/// `allow_types` switches to an allow-list: only responses whose content
+/// type matches one of them are compressed, and responses without a
+/// content type are left alone. `skip_types` still applies on top.
+///
+/// ```rust
+/// use axeon::middleware::{CompressionConfig, CompressionMiddleware};
+/// use axeon::Server;
+///
+/// let mut app = Server::new();
+/// app.middleware(CompressionMiddleware::new(CompressionConfig {
+///     allow_types: Some(vec!["text/".to_string(), "application/json".to_string()]),
+///     ..CompressionConfig::default()
+/// }));
+/// ```
 #[derive(Clone)]
 pub struct CompressionConfig {
     pub level: Compression,
     pub min_size: usize,
     pub skip_types: Vec<String>,
+    /// When set, only these content types are compressed
+    pub allow_types: Option<Vec<String>>,
 }
 
 impl CompressionConfig {
@@ -18,6 +40,15 @@ impl CompressionConfig {
             return false;
         }
 
+        // In allow-list mode, only compress listed content types
+        if let Some(allow_types) = &self.allow_types {
+            let allowed = content_type
+                .is_some_and(|ct| allow_types.iter().any(|allow_type| ct.starts_with(allow_type.as_str())));
+            if !allowed {
+                return false;
+            }
+        }
+
         // Don't compress if content type is in skip list
         if let Some(ct) = content_type {
             for skip_type in &self.skip_types {
@@ -43,10 +74,26 @@ impl Default for CompressionConfig {
                 "application/pdf".to_string(),
                 "application/zip".to_string(),
             ],
+            allow_types: None,
         }
     }
 }
 
+/// Middleware that gzip- or deflate-encodes responses based on the
+/// request's `Accept-Encoding`.
+///
+/// The compressed bytes become the response body as-is and the server
+/// derives `Content-Length` from them.
+///
+/// ```rust,no_run
+/// use axeon::middleware::{CompressionConfig, CompressionMiddleware};
+/// use axeon::{Response, Server};
+///
+/// let mut app = Server::new();
+/// app.middleware(CompressionMiddleware::new(CompressionConfig::default()));
+/// app.get("/", |_req| async { Response::text("hello ".repeat(1000)) });
+/// app.listen("127.0.0.1:3000").unwrap();
+/// ```
 pub struct CompressionMiddleware {
     config: CompressionConfig,
 }
@@ -57,50 +104,113 @@ impl CompressionMiddleware {
     }
 }
 
+// Incremental encoder used for streamed bodies
+enum StreamEncoder {
+    Gzip(GzEncoder<Vec<u8>>),
+    Deflate(DeflateEncoder<Vec<u8>>),
+}
+
+impl StreamEncoder {
+    fn new(encoding: &str, level: Compression) -> Self {
+        match encoding {
+            "gzip" => StreamEncoder::Gzip(GzEncoder::new(Vec::new(), level)),
+            _ => StreamEncoder::Deflate(DeflateEncoder::new(Vec::new(), level)),
+        }
+    }
+
+    // Compresses a chunk and sync-flushes so the client can decode it right away
+    fn compress(&mut self, chunk: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            StreamEncoder::Gzip(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                Ok(std::mem::take(encoder.get_mut()))
+            }
+            StreamEncoder::Deflate(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                Ok(std::mem::take(encoder.get_mut()))
+            }
+        }
+    }
+
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            StreamEncoder::Gzip(encoder) => encoder.finish(),
+            StreamEncoder::Deflate(encoder) => encoder.finish(),
+        }
+    }
+}
+
+fn compress_stream(body: BodyStream, encoding: &str, level: Compression) -> BodyStream {
+    let encoder = StreamEncoder::new(encoding, level);
+    let chunks = stream::unfold(Some((body.0, encoder)), |state| async move {
+        let (mut chunks, mut encoder) = state?;
+        match chunks.next().await {
+            Some(Ok(chunk)) => {
+                let compressed = encoder.compress(&chunk);
+                Some((compressed, Some((chunks, encoder))))
+            }
+            Some(Err(err)) => Some((Err(err), None)),
+            None => Some((encoder.finish(), None)),
+        }
+    });
+    BodyStream(chunks.boxed())
+}
+
 impl Middleware for CompressionMiddleware {
     fn call(&self, req: Request, next: Next) -> MiddlewareResult {
         let config = self.config.clone();
         Box::pin(async move {
-            let mut response = next.handle(req).await?;
-            
             // Get the accepted encodings from the request
-            let accept_encoding = response.headers
+            let accept_encoding = req.headers
                 .get("accept-encoding")
                 .map(|h| h.to_lowercase());
 
-            let content_type = response.headers.get("content-type");
-            let original_body = response.body.clone();
-            let should_compress = config.should_compress(
-                content_type.as_deref().map(|x| x.as_str()),
-                original_body.len()
-            );
-
-            if should_compress {
-                if let Some(accepted) = accept_encoding {
-                    let mut compressed = Vec::new();
-                    
-                    if accepted.contains("gzip") {
+            let mut response = next.handle(req).await?;
+
+            let encoding = match accept_encoding {
+                Some(accepted) if accepted.contains("gzip") => "gzip",
+                Some(accepted) if accepted.contains("deflate") => "deflate",
+                _ => return Ok(response),
+            };
+
+            let content_type = response
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                .map(|(_, value)| value.clone());
+
+            if let Some(body) = response.stream.take() {
+                // Streamed bodies have no known length, so only the content type is checked
+                if config.should_compress(content_type.as_deref(), usize::MAX) {
+                    response.stream = Some(compress_stream(body, encoding, config.level));
+                    response.headers.insert("Content-Encoding".to_string(), encoding.to_string());
+                    add_vary(&mut response);
+                } else {
+                    response.stream = Some(body);
+                }
+                return Ok(response);
+            }
+
+            if config.should_compress(content_type.as_deref(), response.body.len()) {
+                let compressed = match encoding {
+                    "gzip" => {
                         let mut encoder = GzEncoder::new(Vec::new(), config.level);
-                        encoder.write_all(original_body.as_bytes())?;
-                        compressed = encoder.finish()?;
-                        response.headers.insert("Content-Encoding".to_string(), "gzip".to_string());
-                    } else if accepted.contains("deflate") {
+                        encoder.write_all(&response.body)?;
+                        encoder.finish()?
+                    }
+                    _ => {
                         let mut encoder = DeflateEncoder::new(Vec::new(), config.level);
-                        encoder.write_all(original_body.as_bytes())?;
-                        compressed = encoder.finish()?;
-                        response.headers.insert("Content-Encoding".to_string(), "deflate".to_string());
+                        encoder.write_all(&response.body)?;
+                        encoder.finish()?
                     }
+                };
 
-                    if !compressed.is_empty() {
-                        response.body = String::from_utf8_lossy(&compressed).to_string();
-                        response.headers.insert(
-                            "Content-Length".to_string(),
-                            compressed.len().to_string()
-                        );
-                        // Add Vary header to help caches
-                        response.headers.insert("Vary".to_string(), "Accept-Encoding".to_string());
-                    }
-                }
+                // Content-Length is computed by the server from the final body
+                response.body = compressed;
+                response.headers.insert("Content-Encoding".to_string(), encoding.to_string());
+                add_vary(&mut response);
             }
 
             Ok(response)
@@ -110,4 +220,16 @@ impl Middleware for CompressionMiddleware {
     fn clone_box(&self) -> Box<dyn Middleware> {
         Box::new(Self::new(self.config.clone()))
     }
-}
\ No newline at end of file
+}
+
+// Adds Accept-Encoding to Vary to help caches, keeping any set by inner layers
+fn add_vary(response: &mut Response) {
+    let vary = match response.headers.get("Vary") {
+        Some(existing) if !existing.to_lowercase().contains("accept-encoding") => {
+            format!("{}, Accept-Encoding", existing)
+        }
+        Some(existing) => existing.clone(),
+        None => "Accept-Encoding".to_string(),
+    };
+    response.headers.insert("Vary".to_string(), vary);
+}
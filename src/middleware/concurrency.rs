@@ -0,0 +1,127 @@
+use crate::error::ServerError;
+use crate::http::Request;
+use crate::middleware::{client_ip, Middleware, MiddlewareResult, Next};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+type InFlight = Arc<Mutex<HashMap<String, usize>>>;
+
+type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Middleware that limits how many requests from one client IP are handled
+/// at the same time.
+///
+/// The client is identified by the peer address of its connection, like
+/// `RateLimiter`; behind a reverse proxy, list the proxy with
+/// `trust_proxies` to use the `X-Forwarded-For` address it adds instead.
+/// Requests over the limit get a 429, or with `log_only` are handled anyway
+/// and reported to a sink, stderr unless set with `with_sink`. Clones share
+/// their counts.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use axeon::middleware::ConcurrencyLimiter;
+/// use axeon::{Response, Server};
+///
+/// let mut app = Server::new();
+/// app.middleware(ConcurrencyLimiter::new(2));
+/// app.get("/report", |_req| async { Response::text("done") });
+/// app.listen("127.0.0.1:3000").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ConcurrencyLimiter {
+    max_per_client: usize,
+    log_only: bool,
+    trusted_proxies: Vec<IpAddr>,
+    in_flight: InFlight,
+    sink: LogSink,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_per_client: usize) -> Self {
+        Self {
+            max_per_client,
+            log_only: false,
+            trusted_proxies: Vec::new(),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            sink: Arc::new(|line| eprintln!("{}", line)),
+        }
+    }
+
+    /// Lets requests over the limit through and only logs them
+    pub fn log_only(mut self, log_only: bool) -> Self {
+        self.log_only = log_only;
+        self
+    }
+
+    /// Sends the `log_only` reports to `sink` instead of stderr
+    pub fn with_sink<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.sink = Arc::new(sink);
+        self
+    }
+
+    /// Identifies clients by `X-Forwarded-For` when the request comes from
+    /// one of `proxies`. Only list proxies that overwrite or append to it.
+    pub fn trust_proxies(mut self, proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.trusted_proxies = proxies.into_iter().collect();
+        self
+    }
+}
+
+// Counts one in-flight request for a client until dropped, so the slot is
+// released even if the handler fails or the request is cancelled
+struct Slot {
+    client_ip: String,
+    in_flight: InFlight,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(&self.client_ip) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&self.client_ip);
+            }
+        }
+    }
+}
+
+impl Middleware for ConcurrencyLimiter {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        let this = self.clone();
+        Box::pin(async move {
+            let client_ip = client_ip(&req, &this.trusted_proxies).unwrap_or_else(|| "unknown".to_string());
+            let over_limit = {
+                let mut in_flight = this.in_flight.lock().unwrap();
+                let count = in_flight.entry(client_ip.clone()).or_insert(0);
+                *count += 1;
+                *count > this.max_per_client
+            };
+            let _slot = Slot {
+                client_ip: client_ip.clone(),
+                in_flight: Arc::clone(&this.in_flight),
+            };
+
+            if over_limit {
+                if !this.log_only {
+                    return Err(ServerError::TooManyRequests { retry_after: None });
+                }
+                (this.sink)(&format!(
+                    "Concurrency limit of {} exceeded for {}",
+                    this.max_per_client, client_ip
+                ));
+            }
+            next.handle(req).await
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
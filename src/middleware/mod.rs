@@ -1,12 +1,26 @@
 mod security;
+#[cfg(feature = "compression")]
 mod compression;
-
-use crate::http::Request;
-pub use security::{RateLimitConfig, RateLimiter, SecurityConfig, SecurityHeaders, CorsConfig, Cors};
+mod access_log;
+mod basic_auth;
+mod concurrency;
+mod recorder;
+mod size_limit;
+
+use crate::http::{Method, Request};
+use std::net::IpAddr;
+pub use security::{RateLimitAlgorithm, RateLimitConfig, RateLimiter, SecurityConfig, SecurityHeaders, CorsConfig, Cors};
+#[cfg(feature = "compression")]
 pub use compression::{CompressionConfig, CompressionMiddleware};
+pub use access_log::{AccessLog, LogFormat};
+pub use basic_auth::{constant_time_eq, BasicAuthMiddleware};
+pub use concurrency::ConcurrencyLimiter;
+pub use recorder::{RecordedRequest, RequestRecorder};
+pub use size_limit::ResponseSizeLimit;
 
 use crate::handler::{Handler, HttpResponse, IntoResponse};
 use futures::future::BoxFuture;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Next {
@@ -48,6 +62,101 @@ impl Clone for Box<dyn Middleware> {
     }
 }
 
+/// Lets pre-built boxed middleware be registered directly.
+///
+/// ```rust
+/// use axeon::Router;
+/// use axeon::middleware::{Cors, CorsConfig, Middleware, SecurityConfig, SecurityHeaders};
+///
+/// let stack: Vec<Box<dyn Middleware>> = vec![
+///     Box::new(Cors::new(CorsConfig::default())),
+///     Box::new(SecurityHeaders::new(SecurityConfig::default())),
+/// ];
+///
+/// let mut router = Router::new();
+/// for middleware in stack {
+///     router.middleware(middleware);
+/// }
+/// ```
+impl Middleware for Box<dyn Middleware> {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        (**self).call(req, next)
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        (**self).clone_box()
+    }
+}
+
+type PathPredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+// Runs the inner middleware only when the request path matches the predicate
+pub(crate) struct PathFiltered {
+    predicate: PathPredicate,
+    inner: Box<dyn Middleware>,
+}
+
+impl PathFiltered {
+    pub fn new<P>(predicate: P, middleware: impl Middleware + 'static) -> Self
+    where
+        P: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            predicate: Arc::new(predicate),
+            inner: Box::new(middleware),
+        }
+    }
+}
+
+impl Middleware for PathFiltered {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        if (self.predicate)(&req.path) {
+            self.inner.call(req, next)
+        } else {
+            Box::pin(async move { next.handle(req).await })
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(Self {
+            predicate: Arc::clone(&self.predicate),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+// Runs the inner middleware only for the given request methods
+pub(crate) struct MethodFiltered {
+    methods: Vec<Method>,
+    inner: Box<dyn Middleware>,
+}
+
+impl MethodFiltered {
+    pub fn new(methods: &[Method], middleware: impl Middleware + 'static) -> Self {
+        Self {
+            methods: methods.to_vec(),
+            inner: Box::new(middleware),
+        }
+    }
+}
+
+impl Middleware for MethodFiltered {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        if self.methods.contains(&req.method) {
+            self.inner.call(req, next)
+        } else {
+            Box::pin(async move { next.handle(req).await })
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(Self {
+            methods: self.methods.clone(),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct MiddlewareManager {
     pub(crate )middlewares: Vec<Box<dyn Middleware>>,
@@ -106,3 +215,32 @@ macro_rules! middlewares {
         compile_error!("The middlewares! macro requires at least one handler")
     };
 }
+
+// The client a request comes from, for limits and logs: the connected
+// peer, unless it is one of `trusted_proxies`. Then it is the right-most
+// `X-Forwarded-For` hop that isn't a trusted proxy, as anything left of it
+// may have been made up by the client, or else `X-Real-IP`.
+pub(crate) fn client_ip(req: &Request, trusted_proxies: &[IpAddr]) -> Option<String> {
+    let peer = req.remote_addr?.ip();
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer.to_string());
+    }
+
+    let hops: Vec<&str> = req
+        .get_header_all("x-forwarded-for")
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .collect();
+    let untrusted = hops
+        .iter()
+        .rev()
+        .find(|hop| hop.parse().map_or(true, |ip: IpAddr| !trusted_proxies.contains(&ip)));
+    match (untrusted, hops.first()) {
+        (Some(hop), _) => Some(hop.to_string()),
+        // Every hop is a trusted proxy, so the first one is the client
+        (None, Some(first)) => Some(first.to_string()),
+        (None, None) => Some(req.headers.get("x-real-ip").cloned().unwrap_or_else(|| peer.to_string())),
+    }
+}
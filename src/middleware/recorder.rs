@@ -0,0 +1,144 @@
+use crate::handler::HttpResponse;
+use crate::http::{Request, Response};
+use crate::middleware::{Middleware, MiddlewareResult, Next};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// Header values that never end up in a recording
+const REDACTED_HEADERS: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
+/// A request captured by `RequestRecorder`
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path as received, without the query string
+    pub path: String,
+    pub query: String,
+    /// Header lines in arrival order with lowercased names
+    pub headers: Vec<(String, String)>,
+    /// Body up to the recorder's `max_body` limit, lossily decoded as UTF-8
+    pub body: String,
+    pub body_truncated: bool,
+    /// Status of the response the request got
+    pub status: u16,
+}
+
+/// Opt-in middleware that keeps the most recent requests in a ring buffer
+/// for debugging, without changing how they are handled.
+///
+/// Each recording holds the method, path, query, headers, the body up to
+/// `max_body` bytes (default 4 KiB) and the response status. Values of
+/// `Authorization`, `Proxy-Authorization` and `Cookie` are redacted.
+/// Clones share the same buffer, so the recordings can be served with
+/// `endpoint` on a route of your choice.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use axeon::middleware::RequestRecorder;
+/// use axeon::{Response, Server};
+///
+/// let recorder = RequestRecorder::new(100);
+/// let mut app = Server::new();
+/// app.middleware(recorder.clone());
+/// app.get("/users", |_req| async { Response::text("users") });
+/// app.get("/_debug/requests", recorder.endpoint());
+/// app.listen("127.0.0.1:3000").unwrap();
+/// ```
+///
+/// Requests that match no route skip app-level middleware, so `/missing`
+/// above isn't recorded.
+#[derive(Clone)]
+pub struct RequestRecorder {
+    capacity: usize,
+    max_body: usize,
+    entries: Arc<Mutex<VecDeque<RecordedRequest>>>,
+}
+
+impl RequestRecorder {
+    /// Creates a recorder keeping the last `capacity` requests
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            max_body: 4096,
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// Sets how many body bytes are kept per request
+    pub fn max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+        self
+    }
+
+    /// Returns the recorded requests, oldest first
+    pub fn entries(&self) -> Vec<RecordedRequest> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Returns a handler answering with the recorded requests as a JSON
+    /// array, oldest first
+    pub fn endpoint(&self) -> impl Fn(Request) -> BoxFuture<'static, HttpResponse> + Send + Sync + Clone + 'static {
+        let recorder = self.clone();
+        move |_req: Request| {
+            let entries = recorder.entries();
+            Box::pin(async move { Response::ok(&entries) })
+        }
+    }
+
+    fn capture(&self, req: &Request) -> RecordedRequest {
+        let body = req.body.as_bytes();
+        let kept = &body[..body.len().min(self.max_body)];
+        let headers = req
+            .header_lines
+            .iter()
+            .map(|(name, value)| {
+                let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                    "[redacted]".to_string()
+                } else {
+                    value.clone()
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        RecordedRequest {
+            method: format!("{:?}", req.method),
+            path: req.raw_path().to_string(),
+            query: req.raw_query().to_string(),
+            headers,
+            body: String::from_utf8_lossy(kept).into_owned(),
+            body_truncated: kept.len() < body.len(),
+            status: 0,
+        }
+    }
+}
+
+impl Middleware for RequestRecorder {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        let this = self.clone();
+        Box::pin(async move {
+            let mut entry = this.capture(&req);
+            let res = next.handle(req).await;
+            entry.status = match &res {
+                Ok(response) => response.status,
+                Err(err) => err.status_code(),
+            };
+
+            // Recorded once handled, so the endpoint doesn't list itself
+            if this.capacity > 0 {
+                let mut entries = this.entries.lock().unwrap();
+                if entries.len() == this.capacity {
+                    entries.pop_front();
+                }
+                entries.push_back(entry);
+            }
+            res
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
@@ -1,10 +1,10 @@
 use crate::http::{Method, Request};
-use crate::middleware::{Middleware, MiddlewareResult, Next};
+use crate::middleware::{client_ip, Middleware, MiddlewareResult, Next};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use lazy_static::lazy_static;
 use crate::error::ServerError;
 use crate::http::Response;
 
@@ -69,10 +69,23 @@ impl Middleware for SecurityHeaders {
     }
 }
 
+/// How `RateLimiter` decides whether a request is allowed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RateLimitAlgorithm {
+    /// Counts requests in the last minute and rejects once either
+    /// `burst_size` or `requests_per_minute` is reached
+    #[default]
+    SlidingWindow,
+    /// A bucket of `burst_size` tokens refilled at `requests_per_minute`;
+    /// each request takes one, so bursts are allowed up to the bucket size
+    TokenBucket,
+}
+
 #[derive(Clone)]
 pub struct RateLimitConfig {
     pub requests_per_minute: u32,
     pub burst_size: u32,
+    pub algorithm: RateLimitAlgorithm,
 }
 
 impl Default for RateLimitConfig {
@@ -80,45 +93,123 @@ impl Default for RateLimitConfig {
         Self {
             requests_per_minute: 60,
             burst_size: 10,
+            algorithm: RateLimitAlgorithm::SlidingWindow,
         }
     }
 }
 
 
-lazy_static! {
-    // Changed to store (IP, Path) combination
-    static ref REQUESTS: Arc<Mutex<HashMap<(String, String), Vec<Instant>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
+// Request times per (client IP, path)
+type RequestLog = HashMap<(String, String), Vec<Instant>>;
+
+// Token buckets per (client IP, path)
+type Buckets = HashMap<(String, String), Bucket>;
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
 }
 
+/// Middleware that limits requests per client IP and path.
+///
+/// The client is identified by the peer address of its connection, so a
+/// made-up `X-Forwarded-For` doesn't get a fresh limit; behind a reverse
+/// proxy, list the proxy with `trust_proxies`.
+///
+/// Each limiter counts on its own; clones, including the copies the server
+/// makes per route, share the counts of the limiter they came from.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use axeon::middleware::{RateLimitAlgorithm, RateLimitConfig, RateLimiter};
+/// use axeon::{Response, Server};
+///
+/// let mut app = Server::new();
+/// app.middleware(RateLimiter::new(RateLimitConfig {
+///     requests_per_minute: 120,
+///     burst_size: 3,
+///     algorithm: RateLimitAlgorithm::TokenBucket,
+/// }));
+/// app.get("/api", |_req| async { Response::text("ok") });
+/// app.listen("127.0.0.1:3000").unwrap();
+/// ```
+///
+/// Rejected requests get a 429 whose `Retry-After` is the number of seconds
+/// until the limiter would let the client through again: when the oldest
+/// counted request leaves the window, or when the next token is refilled.
 #[derive(Clone)]
 pub struct RateLimiter {
     config: RateLimitConfig,
+    requests: Arc<Mutex<RequestLog>>,
+    buckets: Arc<Mutex<Buckets>>,
+    trusted_proxies: Vec<IpAddr>,
 }
 
 impl RateLimiter {
     pub fn new(config: RateLimitConfig) -> Self {
         Self {
             config,
+            requests: Arc::new(Mutex::new(HashMap::new())),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            trusted_proxies: Vec::new(),
         }
     }
 
-    async fn is_allowed(&self, client_ip: &str, path: &str) -> bool {
-        let mut requests = REQUESTS.lock().await;
+    /// Identifies clients by `X-Forwarded-For` when the request comes from
+    /// one of `proxies`. Only list proxies that overwrite or append to it.
+    pub fn trust_proxies(mut self, proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.trusted_proxies = proxies.into_iter().collect();
+        self
+    }
+
+    // Ok if the request may go ahead, otherwise how long until it would
+    async fn is_allowed(&self, client_ip: &str, path: &str) -> Result<(), Duration> {
+        let key = (client_ip.to_string(), path.to_string());
+        match self.config.algorithm {
+            RateLimitAlgorithm::SlidingWindow => self.window_allows(key).await,
+            RateLimitAlgorithm::TokenBucket => self.bucket_allows(key).await,
+        }
+    }
+
+    async fn bucket_allows(&self, key: (String, String)) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().await;
+        let now = Instant::now();
+        let capacity = self.config.burst_size as f64;
+        let per_second = self.config.requests_per_minute as f64 / 60.0;
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            refilled: now,
+        });
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.refilled = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if per_second > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        } else {
+            Err(Duration::from_secs(60))
+        }
+    }
+
+    async fn window_allows(&self, key: (String, String)) -> Result<(), Duration> {
+        let mut requests = self.requests.lock().await;
         let now = Instant::now();
         let minute_ago = now - Duration::from_secs(60);
-        let key = (client_ip.to_string(), path.to_string());
 
         // Clean up old requests
         if let Some(times) = requests.get_mut(&key) {
             times.retain(|&time| time > minute_ago);
 
-            if times.len() >= self.config.burst_size as usize {
-                return false;
-            }
-
-            if times.len() as u32 >= self.config.requests_per_minute {
-                return false;
+            let limit = self.config.burst_size.min(self.config.requests_per_minute) as usize;
+            if times.len() >= limit {
+                // Wait until enough of the oldest requests leave the window
+                let frees_slot = times.get(times.len() - limit).copied().unwrap_or(now);
+                return Err((frees_slot + Duration::from_secs(60)).saturating_duration_since(now));
             }
 
             times.push(now);
@@ -126,7 +217,7 @@ impl RateLimiter {
             requests.insert(key, vec![now]);
         }
 
-        true
+        Ok(())
     }
 }
 
@@ -134,22 +225,18 @@ impl Middleware for RateLimiter {
     fn call(&self, req: Request, next: Next) -> MiddlewareResult {
         let self_clone = self.clone();
         Box::pin(async move {
-            let client_ip = req.headers.get("x-forwarded-for")
-                .or_else(|| req.headers.get("x-real-ip"))
-                .unwrap_or(&"unknown".to_string())
-                .to_string();
+            let client_ip = client_ip(&req, &self_clone.trusted_proxies).unwrap_or_else(|| "unknown".to_string());
 
             let path = req.path.clone();
-            if self_clone.is_allowed(&client_ip, &path).await {
-                next.handle(req).await
-            } else {
-                Err(ServerError::TooManyRequests)
+            match self_clone.is_allowed(&client_ip, &path).await {
+                Ok(()) => next.handle(req).await,
+                Err(wait) => Err(ServerError::TooManyRequests { retry_after: Some(wait) }),
             }
         })
     }
 
     fn clone_box(&self) -> Box<dyn Middleware> {
-        Box::new(Self::new(self.config.clone()))
+        Box::new(self.clone())
     }
 }
 
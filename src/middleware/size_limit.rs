@@ -0,0 +1,130 @@
+use crate::error::ServerError;
+use crate::http::response::BodyStream;
+use crate::http::Request;
+use crate::middleware::{Middleware, MiddlewareResult, Next};
+use futures::stream::{self, StreamExt};
+use std::io::Error;
+
+/// Middleware that caps the size of response bodies.
+///
+/// Buffered bodies are checked once the handler returns: an oversized body
+/// becomes a 500, or with `truncate` is cut to the limit. Streamed bodies
+/// (see `Response::body_len`) have no size upfront, so by default they are
+/// counted while being sent and the connection is aborted once the limit is
+/// passed, as the status line is already out by then. With `buffer_streams`
+/// they are collected first, up to the limit, so they get the same 500 or
+/// truncation as buffered bodies at the cost of holding them in memory.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use axeon::middleware::ResponseSizeLimit;
+/// use axeon::{Response, Server};
+///
+/// let mut app = Server::new();
+/// app.middleware(ResponseSizeLimit::new(1024 * 1024).buffer_streams(true));
+/// app.get("/export", |_req| async { Response::text("id,name\n") });
+/// app.listen("127.0.0.1:3000").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ResponseSizeLimit {
+    max_bytes: usize,
+    truncate: bool,
+    buffer_streams: bool,
+}
+
+impl ResponseSizeLimit {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            truncate: false,
+            buffer_streams: false,
+        }
+    }
+
+    /// Cuts oversized bodies to the limit instead of failing the request
+    pub fn truncate(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Collects streamed bodies before sending so their size can be checked
+    pub fn buffer_streams(mut self, buffer: bool) -> Self {
+        self.buffer_streams = buffer;
+        self
+    }
+
+    fn too_large(&self) -> ServerError {
+        ServerError::InternalError(format!("Response body exceeds {} bytes", self.max_bytes))
+    }
+
+    // Passes chunks through until the limit, then ends the body early or
+    // fails it so the connection is aborted
+    fn limit_stream(&self, body: BodyStream) -> BodyStream {
+        let max_bytes = self.max_bytes;
+        let truncate = self.truncate;
+        let chunks = stream::unfold(Some((body.0, 0usize)), move |state| async move {
+            let (mut chunks, sent) = state?;
+            match chunks.next().await? {
+                Ok(mut chunk) if sent + chunk.len() > max_bytes => {
+                    if truncate {
+                        chunk.truncate(max_bytes - sent);
+                        Some((Ok(chunk), None))
+                    } else {
+                        let err = Error::other(format!("Response body exceeds {} bytes", max_bytes));
+                        Some((Err(err), None))
+                    }
+                }
+                Ok(chunk) => {
+                    let sent = sent + chunk.len();
+                    Some((Ok(chunk), Some((chunks, sent))))
+                }
+                Err(err) => Some((Err(err), None)),
+            }
+        });
+        BodyStream(chunks.boxed())
+    }
+
+    // Reads a streamed body into memory, stopping one byte past the limit
+    async fn collect_stream(&self, body: BodyStream) -> std::io::Result<Vec<u8>> {
+        let mut chunks = body.0;
+        let mut collected = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            collected.extend_from_slice(&chunk?);
+            if collected.len() > self.max_bytes {
+                collected.truncate(self.max_bytes + 1);
+                break;
+            }
+        }
+        Ok(collected)
+    }
+}
+
+impl Middleware for ResponseSizeLimit {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        let this = self.clone();
+        Box::pin(async move {
+            let mut response = next.handle(req).await?;
+
+            if let Some(body) = response.stream.take() {
+                if !this.buffer_streams {
+                    response.stream = Some(this.limit_stream(body));
+                    return Ok(response);
+                }
+                response.body = this.collect_stream(body).await?;
+            }
+
+            if response.body.len() > this.max_bytes {
+                if !this.truncate {
+                    return Err(this.too_large());
+                }
+                response.body.truncate(this.max_bytes);
+            }
+            Ok(response)
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
@@ -1,25 +1,98 @@
+mod trie;
+
+use crate::error::ServerError;
 use crate::handler::{Handler, HttpResponse, IntoResponse};
 use crate::http::{Method, Request};
-use crate::middleware::{Middleware, MiddlewareManager, Next};
+use crate::middleware::{MethodFiltered, Middleware, MiddlewareManager, Next, PathFiltered};
 use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+pub(crate) use trie::PathTrie;
 
 #[derive(Clone)]
 pub(crate) struct Route {
     pub(crate) middlewares: MiddlewareManager,
     pub(crate) handler: Box<dyn Handler>,
+    pub(crate) timeout: Option<Duration>,
 }
 
 impl Route {
-    pub async fn handle(&self, req: Request) -> HttpResponse {
-        self.middlewares.call(req, Next::new_handler(self.handler.clone())).await
+    // Runs the route until `deadline`, or for its own timeout if it has one
+    pub async fn handle(&self, mut req: Request, deadline: Instant) -> HttpResponse {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout).unwrap_or(deadline);
+        req.deadline = Some(deadline.into_std());
+        let response = self.middlewares.call(req, Next::new_handler(self.handler.clone()));
+        timeout_at(deadline, response)
+            .await
+            .unwrap_or(Err(ServerError::Timeout))
+    }
+}
+
+/// A route entry for `Router::register` and `Server::register`, created
+/// with `route`.
+pub struct RouteDef {
+    method: Method,
+    path: String,
+    handler: Box<dyn Handler>,
+}
+
+/// Describes a route for registration from a table. Handlers of different
+/// types can be mixed in the same table.
+///
+/// # Example
+///
+/// ```rust
+/// use axeon::{route, Method, Request, Response, Server, ServerError};
+///
+/// async fn list_users(_req: Request) -> Result<Response, ServerError> {
+///     Response::text("users")
+/// }
+///
+/// let mut app = Server::new();
+/// app.register(vec![
+///     route(Method::GET, "/users", list_users),
+///     route(Method::POST, "/users", |_req| async { Response::text("created") }),
+///     route(Method::GET, "/health", |_req| async { Response::text("ok") }),
+/// ]);
+/// ```
+pub fn route<F, R>(method: Method, path: &str, handler: F) -> RouteDef
+where
+    F: Fn(Request) -> R + Send + Sync + Clone + 'static,
+    R: IntoResponse + 'static,
+{
+    RouteDef {
+        method,
+        path: path.to_string(),
+        handler: Box::new(handler),
     }
 }
 
+/// How `Server::trailing_slash` treats a trailing slash on request paths
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrailingSlashMode {
+    /// 301-redirect `/foo/` to `/foo`
+    RedirectToNoSlash,
+    /// 301-redirect `/foo` to `/foo/`
+    RedirectToSlash,
+    /// Serve both forms from the same route without redirecting, even on
+    /// `strict_slash` routers
+    Transparent,
+}
+
 #[derive(Clone)]
 pub struct Router {
     pub(crate) middlewares: MiddlewareManager,
     pub(crate) routes: HashMap<String, HashMap<Method, Route>>,
-    pub(crate) dynamic_routes: Vec<String>,
+    pub(crate) dynamic_routes: PathTrie,
+    strict_slash: bool,
+    // Set with route_timeout, kept for routes registered afterwards
+    timeouts: HashMap<(String, Method), Duration>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Router {
@@ -27,7 +100,9 @@ impl Router {
         Self {
             middlewares: MiddlewareManager::new(),
             routes: HashMap::new(),
-            dynamic_routes: Vec::new(),
+            dynamic_routes: PathTrie::default(),
+            strict_slash: false,
+            timeouts: HashMap::new(),
         }
     }
 
@@ -112,35 +187,185 @@ impl Router {
         self
     }
 
+    /// Treats `/users` and `/users/` as distinct routes when `strict` is
+    /// true. By default a trailing slash is ignored both when registering
+    /// and when matching. Call before registering routes.
+    ///
+    /// ```rust
+    /// use axeon::{Response, Router, Server};
+    ///
+    /// let mut router = Router::new();
+    /// router.strict_slash(true);
+    /// // Only `/users` matches; `/users/` is a 404
+    /// router.get("/users", |_req| async { Response::text("users") });
+    ///
+    /// let mut app = Server::new();
+    /// app.use_router(router);
+    /// ```
+    pub fn strict_slash(&mut self, strict: bool) -> &mut Self {
+        self.strict_slash = strict;
+        self
+    }
+
+    pub(crate) fn is_strict_slash(&self) -> bool {
+        self.strict_slash
+    }
+
+    // Registered paths drop trailing slashes unless strict_slash is on
+    fn normalize_path(&self, path: &str) -> String {
+        let path = if self.strict_slash { path } else { path.trim_end_matches('/') };
+        if path.is_empty() { "/".to_string() } else { path.to_string() }
+    }
+
+    // The path `req` is matched by. With strict_slash on, a trailing slash
+    // sent by the client is kept.
+    pub(crate) fn lookup_path(&self, req: &Request) -> String {
+        if self.strict_slash && req.path != "/" && req.raw_path().ends_with('/') {
+            format!("{}/", req.path)
+        } else {
+            req.path.clone()
+        }
+    }
+
+    /// Registers every route in a declarative table, see `route`
+    pub fn register<I>(&mut self, routes: I) -> &mut Self
+    where
+        I: IntoIterator<Item = RouteDef>,
+    {
+        for def in routes {
+            self.add_boxed(def.method, &def.path, def.handler);
+        }
+        self
+    }
+
     fn add<F, R>(&mut self, method: Method, path: &str, handler: F)
     where
         F: Fn(Request) -> R + Send + Sync + Clone + 'static,
         R: IntoResponse,
     {
-        let path = path.trim_end_matches('/').to_owned();
-        let path = if path.is_empty() { "/".to_owned() } else { path };
+        self.add_boxed(method, path, Box::new(handler));
+    }
+
+    fn add_boxed(&mut self, method: Method, path: &str, handler: Box<dyn Handler>) {
+        let path = self.normalize_path(path);
         if !self.routes.contains_key(&path) {
             self.routes.insert(path.clone(), HashMap::new());
         }
         if path.contains(":") {
-            self.dynamic_routes.push(path.clone());
+            self.dynamic_routes.insert(&path);
         }
+        let timeout = self.timeouts.get(&(path.clone(), method)).copied();
         self.routes
             .get_mut(&path)
             .unwrap()
             .insert(method, Route {
                 middlewares: self.middlewares.clone(),
-                handler: Box::new(handler),
+                handler,
+                timeout,
             });
     }
 
+    // Methods registered for `path`, from its static entry and any dynamic
+    // pattern matching it
+    pub(crate) fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        let mut methods: Vec<Method> = self
+            .routes
+            .get(path)
+            .into_iter()
+            .chain(
+                self.dynamic_routes
+                    .matches(path)
+                    .into_iter()
+                    .filter_map(|(pattern, _)| self.routes.get(pattern)),
+            )
+            .flat_map(|routes| routes.keys().copied())
+            .collect();
+        methods.sort_by_key(|method| *method as u8);
+        methods.dedup();
+        methods
+    }
+
+    /// Sets a timeout for the route for `method` at `path`, replacing the
+    /// server's `request_timeout` for its handler. Can be called before or
+    /// after the route is registered.
+    pub fn route_timeout(&mut self, method: Method, path: &str, timeout: Duration) -> &mut Self {
+        let path = self.normalize_path(path);
+        if let Some(route) = self.routes.get_mut(&path).and_then(|routes| routes.get_mut(&method)) {
+            route.timeout = Some(timeout);
+        }
+        self.timeouts.insert((path, method), timeout);
+        self
+    }
+
+    // Prefixes every route's middleware chain with `middlewares`
+    pub(crate) fn with_middlewares(mut self, middlewares: &MiddlewareManager) -> Self {
+        for routes in self.routes.values_mut() {
+            for route in routes.values_mut() {
+                let mut chain = middlewares.clone();
+                chain.append(route.middlewares.clone());
+                route.middlewares = chain;
+            }
+        }
+        self
+    }
+
     pub fn middleware(&mut self, middleware: impl Middleware + 'static) {
         self.middlewares.add(middleware);
     }
 
+    pub fn middleware_if<P>(&mut self, predicate: P, middleware: impl Middleware + 'static)
+    where
+        P: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.middlewares.add(PathFiltered::new(predicate, middleware));
+    }
+
+    /// Adds a middleware that only runs for requests using one of `methods`.
+    /// HEAD requests are matched as GET.
+    ///
+    /// ```rust
+    /// use axeon::{Method, Response, Router};
+    /// use axeon::middleware::{RateLimiter, RateLimitConfig};
+    ///
+    /// let mut api = Router::new();
+    /// api.middleware_for(
+    ///     &[Method::POST, Method::PUT, Method::DELETE],
+    ///     RateLimiter::new(RateLimitConfig::default()),
+    /// );
+    /// api.get("/items", |_req| async { Response::text("items") });
+    /// api.post("/items", |_req| async { Response::text("created") });
+    /// ```
+    pub fn middleware_for(&mut self, methods: &[Method], middleware: impl Middleware + 'static) {
+        self.middlewares.add(MethodFiltered::new(methods, middleware));
+    }
+
+    /// Mounts `router` under `path`. Mounted routes keep the middleware that
+    /// was registered on `router` before them (such as a router-specific
+    /// `Cors`), prefixed by this router's middleware.
+    ///
+    /// ```rust
+    /// use axeon::{Response, Router, Server};
+    /// use axeon::middleware::{Cors, CorsConfig};
+    ///
+    /// let mut public = Router::new();
+    /// public.middleware(Cors::new(CorsConfig::default()));
+    /// public.get("/status", |_req| async { Response::text("ok") });
+    ///
+    /// let mut admin = Router::new();
+    /// admin.middleware(Cors::new(CorsConfig {
+    ///     allow_origins: vec!["https://admin.example.com".to_string()],
+    ///     ..CorsConfig::default()
+    /// }));
+    /// admin.post("/users", |_req| async { Response::text("created") });
+    ///
+    /// let mut app = Server::new();
+    /// app.mount("/api", public);
+    /// app.mount("/admin", admin);
+    /// ```
     pub fn mount(&mut self, path: &str, router: Router) {
         for (key, value) in router.routes.into_iter() {
-            let path = (path.to_owned() + &key).trim_end_matches('/').to_owned();
+            let path = if key == "/" { path.to_owned() } else { path.to_owned() + &key };
+            let path = self.normalize_path(&path);
 
             for (method, handler) in value {
                 if !self.routes.contains_key(&path) {
@@ -148,7 +373,7 @@ impl Router {
                 }
 
                 if path.contains(":") {
-                    self.dynamic_routes.push(path.clone());
+                    self.dynamic_routes.insert(&path);
                 }
 
                 self.routes
@@ -157,6 +382,7 @@ impl Router {
                     .insert(method, Route {
                         middlewares: self.middlewares.clone().append(handler.middlewares.clone()).clone(),
                         handler: handler.handler,
+                        timeout: handler.timeout,
                     });
             }
         }
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+// Index of dynamic route patterns by path segment. `:name` segments match
+// any single segment and capture it under `name`. Paths are split while still
+// percent-encoded, so `%2F` never separates segments; captures are decoded
+// afterwards.
+#[derive(Clone, Default)]
+pub(crate) struct PathTrie {
+    root: Node,
+    len: usize,
+}
+
+#[derive(Clone, Default)]
+struct Node {
+    statics: HashMap<String, Node>,
+    params: Vec<(String, Node)>,
+    // Registration order and the pattern ending at this node
+    pattern: Option<(usize, String)>,
+}
+
+impl PathTrie {
+    pub(crate) fn insert(&mut self, pattern: &str) {
+        let mut node = &mut self.root;
+        for segment in pattern.split('/') {
+            node = match segment.strip_prefix(':') {
+                Some(name) => {
+                    let index = match node.params.iter().position(|(param, _)| param == name) {
+                        Some(index) => index,
+                        None => {
+                            node.params.push((name.to_string(), Node::default()));
+                            node.params.len() - 1
+                        }
+                    };
+                    &mut node.params[index].1
+                }
+                None => node.statics.entry(segment.to_string()).or_default(),
+            };
+        }
+        if node.pattern.is_none() {
+            node.pattern = Some((self.len, pattern.to_string()));
+            self.len += 1;
+        }
+    }
+
+    // Every pattern matching `path` with its captured params, in the order
+    // the patterns were registered
+    pub(crate) fn matches(&self, path: &str) -> Vec<(&str, HashMap<String, String>)> {
+        let segments: Vec<&str> = path.split('/').collect();
+        let mut found = Vec::new();
+        Self::collect(&self.root, &segments, &mut Vec::new(), &mut found);
+        found.sort_by_key(|(index, _, _)| *index);
+        found
+            .into_iter()
+            .map(|(_, pattern, params)| (pattern, params))
+            .collect()
+    }
+
+    fn collect<'a>(
+        node: &'a Node,
+        segments: &[&str],
+        captured: &mut Vec<(&'a str, String)>,
+        found: &mut Vec<(usize, &'a str, HashMap<String, String>)>,
+    ) {
+        let Some((segment, rest)) = segments.split_first() else {
+            if let Some((index, pattern)) = &node.pattern {
+                let params = captured
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), decode_segment(value)))
+                    .collect();
+                found.push((*index, pattern, params));
+            }
+            return;
+        };
+
+        if let Some(child) = node.statics.get(*segment) {
+            Self::collect(child, rest, captured, found);
+        }
+        for (name, child) in &node.params {
+            // Decoded once the whole pattern has matched
+            captured.push((name, segment.to_string()));
+            Self::collect(child, rest, captured, found);
+            captured.pop();
+        }
+    }
+}
+
+// Percent-decodes a captured segment. `+` is kept as is since it only means
+// a space in query strings; invalid UTF-8 is replaced lossily.
+fn decode_segment(segment: &str) -> String {
+    match urlencoding::decode(segment) {
+        Ok(decoded) => decoded.into_owned(),
+        Err(_) => String::from_utf8_lossy(&urlencoding::decode_binary(segment.as_bytes())).into_owned(),
+    }
+}
@@ -0,0 +1,146 @@
+//! Helpers for tests that talk to a running server over TCP.
+#![allow(dead_code)]
+
+use axeon::Server;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread::JoinHandle;
+
+/// Starts `app` on an ephemeral port and returns its address. The listener
+/// is bound before this returns, so clients can connect right away.
+pub fn serve(app: Server) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || app.serve(listener).ok());
+    addr
+}
+
+/// Like `serve`, returning the server thread so the caller can wait for it
+/// after `shutdown` resolves.
+pub fn serve_with_shutdown<F>(app: Server, shutdown: F) -> (SocketAddr, JoinHandle<Result<(), String>>)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || app.serve_with_shutdown(listener, shutdown).map_err(|e| e.to_string()));
+    (addr, server)
+}
+
+/// A response as read off the wire.
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The body with any chunked framing removed
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// The first value of the header `name`, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Every value of the header `name`, in the order they were sent
+    pub fn headers_all(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+/// Writes `raw` on a new connection and reads everything until the server
+/// closes it.
+pub fn send_raw(addr: SocketAddr, raw: &[u8]) -> Vec<u8> {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(raw).unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    response
+}
+
+/// Writes `raw` on a new connection and parses the single response to it.
+/// The request should ask for `Connection: close` if the body has no length.
+pub fn send(addr: SocketAddr, raw: &str) -> HttpResponse {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(raw.as_bytes()).unwrap();
+    read_response(&mut BufReader::new(stream))
+}
+
+/// Sends a `GET` for `path` with `Connection: close`
+pub fn get(addr: SocketAddr, path: &str) -> HttpResponse {
+    send(addr, &format!("GET {} HTTP/1.1\r\nConnection: close\r\n\r\n", path))
+}
+
+/// Sends a `GET` for `path` with extra header lines, each ending in `\r\n`
+pub fn get_with(addr: SocketAddr, path: &str, headers: &str) -> HttpResponse {
+    send(addr, &format!("GET {} HTTP/1.1\r\n{}Connection: close\r\n\r\n", path, headers))
+}
+
+/// Reads one response from a connection, so several can be read in turn
+/// from a keep-alive connection. Interim 1xx responses are returned like
+/// any other.
+pub fn read_response<R: BufRead>(reader: &mut R) -> HttpResponse {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .unwrap_or_else(|| panic!("malformed status line {:?}", line));
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (key, value) = line.split_once(':').unwrap();
+        headers.push((key.to_string(), value.trim().to_string()));
+    }
+
+    let mut response = HttpResponse {
+        status,
+        headers,
+        body: Vec::new(),
+    };
+    if (100..200).contains(&status) || status == 204 || status == 304 {
+        return response;
+    }
+
+    if response.header("Transfer-Encoding") == Some("chunked") {
+        loop {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            let size = usize::from_str_radix(line.trim_end(), 16).unwrap();
+            if size == 0 {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                break;
+            }
+            let start = response.body.len();
+            response.body.resize(start + size + 2, 0);
+            reader.read_exact(&mut response.body[start..]).unwrap();
+            response.body.truncate(start + size);
+        }
+    } else if let Some(length) = response.header("Content-Length") {
+        let mut body = vec![0; length.parse().unwrap()];
+        reader.read_exact(&mut body).unwrap();
+        response.body = body;
+    } else {
+        reader.read_to_end(&mut response.body).unwrap();
+    }
+    response
+}
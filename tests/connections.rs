@@ -0,0 +1,195 @@
+mod common;
+
+use axeon::{Response, Server};
+use common::{get, serve, serve_with_shutdown};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[test]
+fn max_connections_queues_clients_past_the_limit() {
+    let mut app = Server::new();
+    app.max_connections(2);
+    app.get("/", |_req| async { Response::text("ok") });
+    let addr = serve(app);
+
+    // Two keep-alive connections that have been served use up the limit
+    let mut first = TcpStream::connect(addr).unwrap();
+    first.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    first.read_exact(&mut [0; 1]).unwrap();
+    let mut second = TcpStream::connect(addr).unwrap();
+    second.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    second.read_exact(&mut [0; 1]).unwrap();
+
+    let mut third = TcpStream::connect(addr).unwrap();
+    third.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    third.set_read_timeout(Some(Duration::from_millis(300))).unwrap();
+    let err = third.read(&mut [0; 1024]).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut));
+
+    // Closing one frees a slot for the waiting client
+    drop(first);
+    third.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut raw = String::new();
+    third.read_to_string(&mut raw).unwrap();
+    assert!(raw.starts_with("HTTP/1.1 200"));
+}
+
+#[test]
+fn active_connections_counts_open_connections() {
+    let mut app = Server::new();
+    let active = app.active_connections();
+    app.get("/metrics", move |_req| {
+        let active = active.load(Ordering::SeqCst);
+        async move { Response::text(active.to_string()) }
+    });
+    let addr = serve(app);
+
+    // Two served keep-alive connections plus the one asking
+    let mut idle = Vec::new();
+    for _ in 0..2 {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        stream.read_exact(&mut [0; 1]).unwrap();
+        idle.push(stream);
+    }
+    assert_eq!(get(addr, "/metrics").text(), "3");
+
+    drop(idle);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while get(addr, "/metrics").text() != "1" {
+        assert!(Instant::now() < deadline, "closed connections were never released");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn max_in_flight_caps_concurrent_handlers() {
+    static RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    let mut app = Server::new();
+    app.max_in_flight(2);
+    app.get("/work", |_req| async {
+        let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+        PEAK.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+        Response::text("done")
+    });
+    let addr = serve(app);
+
+    let clients: Vec<_> = (0..8).map(|_| std::thread::spawn(move || get(addr, "/work").status)).collect();
+    for client in clients {
+        assert_eq!(client.join().unwrap(), 200);
+    }
+    assert_eq!(PEAK.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn reject_when_busy_answers_503() {
+    let (started, handler_started) = std::sync::mpsc::channel::<()>();
+    let started = Arc::new(Mutex::new(started));
+    let mut app = Server::new();
+    app.max_in_flight(1).reject_when_busy(true);
+    app.get("/work", move |_req| {
+        started.lock().unwrap().send(()).ok();
+        async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            Response::text("done")
+        }
+    });
+    let addr = serve(app);
+
+    let first = std::thread::spawn(move || get(addr, "/work").status);
+    handler_started.recv().unwrap();
+    assert_eq!(get(addr, "/work").status, 503);
+    assert_eq!(first.join().unwrap(), 200);
+}
+
+#[test]
+fn shutdown_stops_the_server_and_closes_idle_connections() {
+    let mut app = Server::new();
+    app.get("/", |_req| async { Response::text("ok") });
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let (addr, server) = serve_with_shutdown(app, async {
+        stopped.await.ok();
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let mut raw = Vec::new();
+    let mut buf = [0; 1024];
+    while !raw.ends_with(b"ok") {
+        let n = stream.read(&mut buf).unwrap();
+        raw.extend_from_slice(&buf[..n]);
+    }
+
+    let started = Instant::now();
+    stop.send(()).unwrap();
+    assert!(server.join().unwrap().is_ok());
+    assert!(started.elapsed() < Duration::from_secs(2));
+    // The idle connection was closed by the server
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn write_failures_reach_on_connection_error() {
+    let (sender, errors) = std::sync::mpsc::channel();
+    let sender = Mutex::new(sender);
+    let mut app = Server::new();
+    app.on_connection_error(move |err| sender.lock().unwrap().send(err.kind()).unwrap());
+    app.get("/large", |_req| async { Response::text("x".repeat(16 * 1024 * 1024)) });
+    let addr = serve(app);
+
+    // The client leaves without reading, so writing the body fails
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /large HTTP/1.1\r\n\r\n").unwrap();
+    drop(stream);
+
+    let kind = errors.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(
+        matches!(kind, ErrorKind::BrokenPipe | ErrorKind::ConnectionReset),
+        "unexpected error kind {:?}",
+        kind
+    );
+}
+
+#[test]
+fn in_flight_requests_past_the_drain_timeout_are_cancelled() {
+    let (sender, errors) = std::sync::mpsc::channel();
+    let sender = Mutex::new(sender);
+    let (started, handler_started) = std::sync::mpsc::channel::<()>();
+    let started = Arc::new(Mutex::new(started));
+    let mut app = Server::new();
+    app.drain_timeout(Duration::from_millis(300));
+    app.on_connection_error(move |err| sender.lock().unwrap().send(err.kind()).unwrap());
+    app.get("/slow", move |_req| {
+        started.lock().unwrap().send(()).ok();
+        async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Response::text("done")
+        }
+    });
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let (addr, server) = serve_with_shutdown(app, async {
+        stopped.await.ok();
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /slow HTTP/1.1\r\n\r\n").unwrap();
+    handler_started.recv().unwrap();
+
+    let started = Instant::now();
+    stop.send(()).unwrap();
+    assert!(server.join().unwrap().is_ok());
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(errors.recv_timeout(Duration::from_secs(1)).unwrap(), ErrorKind::TimedOut);
+
+    // The connection was dropped without a response
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw).ok();
+    assert!(raw.is_empty());
+}
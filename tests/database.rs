@@ -0,0 +1,81 @@
+#![cfg(feature = "database")]
+
+use axeon::database::{Connection, ConnectionPool, DatabaseError, PoolConfig};
+use std::sync::mpsc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+struct Conn;
+
+impl Connection for Conn {
+    fn is_valid(&self) -> bool {
+        true
+    }
+    fn close(&mut self) {}
+}
+
+#[test]
+fn the_pool_is_usable_while_the_factory_runs() {
+    let (connected, wait_for_connect) = mpsc::channel::<()>();
+    let (proceed, factory_waits) = mpsc::channel::<()>();
+    let factory_waits = Mutex::new(factory_waits);
+    let config = PoolConfig {
+        min_idle: 0,
+        ..PoolConfig::default()
+    };
+    let pool = Arc::new(ConnectionPool::new(config, move || {
+        connected.send(()).ok();
+        // Only goes ahead once the test could use the pool meanwhile
+        match factory_waits.lock().unwrap().recv_timeout(Duration::from_secs(5)) {
+            Ok(()) => Ok(Conn),
+            Err(_) => Err(DatabaseError::ConnectionFailed),
+        }
+    }));
+
+    let slow = std::thread::spawn({
+        let pool = Arc::clone(&pool);
+        move || pool.get().map(|_| ())
+    });
+    wait_for_connect.recv().unwrap();
+
+    pool.release(Conn);
+    assert!(pool.get().is_ok());
+    proceed.send(()).unwrap();
+    assert!(slow.join().unwrap().is_ok());
+}
+
+#[test]
+fn a_slow_factory_call_does_not_block_other_connects() {
+    let (connected, wait_for_connect) = mpsc::channel::<()>();
+    let (proceed, factory_waits) = mpsc::channel::<()>();
+    let factory_waits = Mutex::new(factory_waits);
+    let calls = AtomicUsize::new(0);
+    let config = PoolConfig {
+        min_idle: 0,
+        ..PoolConfig::default()
+    };
+    let pool = Arc::new(ConnectionPool::new(config, move || {
+        if calls.fetch_add(1, Ordering::SeqCst) > 0 {
+            return Ok(Conn);
+        }
+        // The first call hangs until the test has connected past it
+        connected.send(()).ok();
+        match factory_waits.lock().unwrap().recv_timeout(Duration::from_secs(5)) {
+            Ok(()) => Ok(Conn),
+            Err(_) => Err(DatabaseError::ConnectionFailed),
+        }
+    }));
+
+    let slow = std::thread::spawn({
+        let pool = Arc::clone(&pool);
+        move || pool.get().map(|_| ())
+    });
+    wait_for_connect.recv().unwrap();
+
+    let started = Instant::now();
+    assert!(pool.get().is_ok());
+    assert!(started.elapsed() < Duration::from_secs(1));
+    proceed.send(()).unwrap();
+    assert!(slow.join().unwrap().is_ok());
+}
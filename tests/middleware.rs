@@ -0,0 +1,611 @@
+mod common;
+
+use axeon::middleware::{
+    ConcurrencyLimiter, Middleware, MiddlewareResult, Next, RateLimitAlgorithm, RateLimitConfig, RateLimiter,
+    RequestRecorder, ResponseSizeLimit,
+};
+use axeon::{Method, Request, Response, Router, Server};
+use common::{get, get_with, send, serve};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Clone)]
+struct Counter(Arc<AtomicUsize>);
+
+impl Middleware for Counter {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move { next.handle(req).await })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
+
+// Adds its name to the response's X-Ran header on the way out
+#[derive(Clone)]
+struct Tag(&'static str);
+
+impl Middleware for Tag {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        let name = self.0;
+        Box::pin(async move {
+            let mut response = next.handle(req).await?;
+            response.append_header("X-Ran", name);
+            Ok(response)
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn middleware_for_only_runs_for_the_listed_methods() {
+    let mut api = Router::new();
+    api.middleware(Tag("all"));
+    api.middleware_for(&[Method::POST, Method::PUT, Method::DELETE], Tag("writes"));
+    api.get("/items", |_req| async { Response::text("items") });
+    api.post("/items", |_req| async { Response::text("created") });
+    api.put("/items", |_req| async { Response::text("replaced") });
+    api.delete("/items", |_req| async { Response::text("deleted") });
+    let mut app = Server::new();
+    app.mount("/api", api);
+    let addr = serve(app);
+
+    let ran = |method: &str| {
+        let response = send(addr, &format!("{} /api/items HTTP/1.1\r\nConnection: close\r\n\r\n", method));
+        assert_eq!(response.status, 200);
+        response.headers_all("X-Ran").join(" ")
+    };
+    assert_eq!(ran("GET"), "all");
+    assert_eq!(ran("POST"), "writes all");
+    assert_eq!(ran("PUT"), "writes all");
+    assert_eq!(ran("DELETE"), "writes all");
+}
+
+#[test]
+fn boxed_middleware_registered_in_a_loop_runs_in_order() {
+    let stack: Vec<Box<dyn Middleware>> = vec![Box::new(Tag("outer")), Box::new(Tag("inner"))];
+    let mut app = Server::new();
+    for middleware in stack {
+        app.middleware(middleware);
+    }
+    app.get("/", |_req| async { Response::text("ok") });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/").headers_all("X-Ran"), ["inner", "outer"]);
+}
+
+#[test]
+fn basic_auth_challenges_missing_and_wrong_credentials() {
+    use axeon::middleware::BasicAuthMiddleware;
+    use base64::Engine;
+
+    let mut app = Server::new();
+    app.middleware(BasicAuthMiddleware::single_user("admin", "secret").realm("admin"));
+    app.get("/admin", |req| async move {
+        Response::text(format!("hello {}", req.get_data("user").and_then(|user| user.as_str()).unwrap_or_default()))
+    });
+    let addr = serve(app);
+    let basic = |credentials: &str| {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+        get_with(addr, "/admin", &format!("Authorization: Basic {}\r\n", encoded))
+    };
+
+    let missing = get(addr, "/admin");
+    assert_eq!(missing.status, 401);
+    assert!(missing.header("WWW-Authenticate").unwrap().starts_with("Basic realm=\"admin\""));
+    for wrong in ["admin:guess", "admin:secret2", "root:secret", "admin"] {
+        let response = basic(wrong);
+        assert_eq!(response.status, 401, "{}", wrong);
+        assert!(response.header("WWW-Authenticate").unwrap().starts_with("Basic realm="));
+    }
+    assert_eq!(get_with(addr, "/admin", "Authorization: Bearer token\r\n").status, 401);
+
+    let ok = basic("admin:secret");
+    assert_eq!(ok.status, 200);
+    assert_eq!(ok.text(), "hello admin");
+}
+
+#[test]
+fn access_log_writes_the_request_line_as_received() {
+    use axeon::middleware::{AccessLog, LogFormat};
+    use std::sync::Mutex;
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let (combined, json) = (Arc::clone(&lines), Arc::clone(&lines));
+    let mut app = Server::new();
+    app.global_middleware(AccessLog::new(LogFormat::Combined).with_sink(move |line| {
+        combined.lock().unwrap().push(line.to_string())
+    }));
+    app.global_middleware(AccessLog::new(LogFormat::Json).with_sink(move |line| {
+        json.lock().unwrap().push(line.to_string())
+    }));
+    app.get("/report", |_req| async { Response::text("hello") });
+    app.get("/export", |_req| async {
+        Response::from_stream(futures::stream::iter([Ok(b"a,b\n".to_vec())]), "text/csv")
+    });
+    let addr = serve(app);
+
+    send(addr, "GET /report HTTP/1.0\r\nUser-Agent: curl/8.0\r\n\r\n");
+    get(addr, "/missing");
+    get(addr, "/report/?format=csv&q=a%20b");
+    get(addr, "/export");
+
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 8);
+    // The inner JSON log finishes first
+    let json: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+    assert_eq!((json["method"].as_str(), json["path"].as_str()), (Some("GET"), Some("/report")));
+    assert_eq!((json["status"].as_u64(), json["bytes"].as_u64()), (Some(200), Some(5)));
+    assert_eq!(json["user_agent"], "curl/8.0");
+    assert!(lines[1].starts_with("127.0.0.1 - - ["), "{}", lines[1]);
+    assert!(lines[1].contains("] \"GET /report HTTP/1.0\" 200 5 \"-\" \"curl/8.0\" "), "{}", lines[1]);
+    assert!(lines[3].contains("] \"GET /missing HTTP/1.1\" 404 "), "{}", lines[3]);
+    // Trailing slash and query string as sent
+    assert!(lines[5].contains("] \"GET /report/?format=csv&q=a%20b HTTP/1.1\" 200 "), "{}", lines[5]);
+    // A streamed body's size isn't known when the line is written
+    let json: serde_json::Value = serde_json::from_str(&lines[6]).unwrap();
+    assert!(json["bytes"].is_null());
+    assert!(lines[7].contains("] \"GET /export HTTP/1.1\" 200 - \"-\" "), "{}", lines[7]);
+}
+
+#[test]
+fn access_log_ignores_forged_client_ips_and_escapes_quoted_fields() {
+    use axeon::middleware::{AccessLog, LogFormat};
+    use std::sync::Mutex;
+
+    let logged = |log: AccessLog, headers: &str| {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        let mut app = Server::new();
+        app.middleware(log.with_sink(move |line| sink.lock().unwrap().push(line.to_string())));
+        app.get("/", |_req| async { Response::text("ok") });
+        get_with(serve(app), "/", headers);
+        let line = lines.lock().unwrap().remove(0);
+        line
+    };
+
+    let forged = "X-Forwarded-For: 1.2.3.4 \"x\"\r\nUser-Agent: a\" 200 0 \"b\\\r\n";
+    let line = logged(AccessLog::new(LogFormat::Combined), forged);
+    assert!(line.starts_with("127.0.0.1 - - ["), "{}", line);
+    assert!(line.contains(r#" "-" "a\" 200 0 \"b\\" "#), "{}", line);
+
+    let proxied = logged(
+        AccessLog::new(LogFormat::Combined).trust_proxies(["127.0.0.1".parse().unwrap()]),
+        "X-Forwarded-For: 203.0.113.9\r\n",
+    );
+    assert!(proxied.starts_with("203.0.113.9 - - ["), "{}", proxied);
+}
+
+// Times the rest of the chain and records it once the response is back
+#[derive(Clone)]
+struct Timed(&'static str);
+
+impl Middleware for Timed {
+    fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+        let name = self.0;
+        let timing = req.server_timing();
+        Box::pin(async move {
+            let started = Instant::now();
+            let response = next.handle(req).await;
+            timing.add(name, started.elapsed());
+            response
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn server_timing_lists_each_layer_in_the_order_it_finished() {
+    let mut app = Server::new();
+    app.middleware(Timed("total"));
+    app.middleware(Timed("routing"));
+    app.get("/report", |req| async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        req.add_timing("db", Duration::from_millis(20));
+        Response::text("report")
+    });
+    let addr = serve(app);
+
+    let header = get(addr, "/report").header("Server-Timing").unwrap().to_string();
+    let entries: Vec<(&str, f64)> = header
+        .split(", ")
+        .map(|entry| {
+            let (name, duration) = entry.split_once(";dur=").unwrap();
+            (name, duration.parse().unwrap())
+        })
+        .collect();
+    let names: Vec<&str> = entries.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["db", "routing", "total"]);
+    assert_eq!(entries[0].1, 20.0);
+    // Each layer took at least as long as the layers inside it
+    assert!(entries[1].1 >= 20.0 && entries[2].1 >= entries[1].1);
+}
+
+#[test]
+fn global_middleware_also_sees_unmatched_requests() {
+    let (routes_only, global) = (Counter(Arc::default()), Counter(Arc::default()));
+    let mut app = Server::new();
+    app.middleware(routes_only.clone());
+    app.global_middleware(global.clone());
+    app.get("/hello", |_req| async { Response::text("hi") });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/hello").status, 200);
+    assert_eq!(get(addr, "/missing").status, 404);
+    assert_eq!(routes_only.0.load(Ordering::SeqCst), 1);
+    assert_eq!(global.0.load(Ordering::SeqCst), 2);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn compression_round_trips_and_respects_allow_types() {
+    use axeon::middleware::{CompressionConfig, CompressionMiddleware};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    let mut app = Server::new();
+    app.middleware(CompressionMiddleware::new(CompressionConfig {
+        allow_types: Some(vec!["text/".to_string(), "application/json".to_string()]),
+        ..CompressionConfig::default()
+    }));
+    app.get("/text", |_req| async { Response::text("hello ".repeat(1000)) });
+    app.get("/json", |_req| async { Response::ok(&vec!["item"; 500]) });
+    app.get("/custom", |_req| async {
+        let mut response = Response::new(200);
+        response.header("Content-Type", "application/custom").body("x".repeat(5000));
+        Ok(response)
+    });
+    let addr = serve(app);
+
+    let text = get_with(addr, "/text", "Accept-Encoding: gzip\r\n");
+    assert_eq!(text.header("Content-Encoding"), Some("gzip"));
+    let mut decoded = String::new();
+    GzDecoder::new(&text.body[..]).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, "hello ".repeat(1000));
+
+    assert_eq!(get_with(addr, "/json", "Accept-Encoding: gzip\r\n").header("Content-Encoding"), Some("gzip"));
+    assert_eq!(get_with(addr, "/custom", "Accept-Encoding: gzip\r\n").header("Content-Encoding"), None);
+}
+
+#[test]
+fn concurrency_limiter_rejects_past_the_limit() {
+    static STARTED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut app = Server::new();
+    app.middleware(ConcurrencyLimiter::new(2));
+    app.get("/report", |_req| async {
+        STARTED.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        Response::text("done")
+    });
+    let addr = serve(app);
+
+    let running: Vec<_> = (0..2).map(|_| std::thread::spawn(move || get(addr, "/report").status)).collect();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while STARTED.load(Ordering::SeqCst) < 2 {
+        assert!(Instant::now() < deadline);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(get(addr, "/report").status, 429);
+    for request in running {
+        assert_eq!(request.join().unwrap(), 200);
+    }
+}
+
+// Serves `/hold`, which takes 500ms, and `/now`; returns a function that
+// waits until a `/hold` request is being handled
+fn concurrency_limited(limiter: ConcurrencyLimiter) -> (std::net::SocketAddr, impl Fn()) {
+    let started = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&started);
+    let mut app = Server::new();
+    app.middleware(limiter);
+    app.get("/hold", move |_req| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            Response::text("done")
+        }
+    });
+    app.get("/now", |_req| async { Response::text("done") });
+    let wait_for_hold = move || {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while started.load(Ordering::SeqCst) == 0 {
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    };
+    (serve(app), wait_for_hold)
+}
+
+#[test]
+fn concurrency_limiter_ignores_forwarding_headers_by_default() {
+    let (addr, wait_for_hold) = concurrency_limited(ConcurrencyLimiter::new(1));
+
+    let held = std::thread::spawn(move || get_with(addr, "/hold", "X-Forwarded-For: 203.0.113.7\r\n").status);
+    wait_for_hold();
+    // A made-up address doesn't make the same socket peer a new client
+    assert_eq!(get_with(addr, "/now", "X-Forwarded-For: 198.51.100.1\r\n").status, 429);
+    assert_eq!(get_with(addr, "/now", "X-Real-IP: 198.51.100.2\r\n").status, 429);
+    assert_eq!(held.join().unwrap(), 200);
+}
+
+#[test]
+fn concurrency_limiter_uses_the_right_most_untrusted_hop() {
+    let proxies: [IpAddr; 2] = ["127.0.0.1".parse().unwrap(), "10.0.0.9".parse().unwrap()];
+    let (addr, wait_for_hold) = concurrency_limited(ConcurrencyLimiter::new(1).trust_proxies(proxies));
+
+    let held = std::thread::spawn(move || {
+        get_with(addr, "/hold", "X-Forwarded-For: 203.0.113.7, 10.0.0.9\r\n").status
+    });
+    wait_for_hold();
+    // Hops the client prepends are ignored
+    let spoofed = "X-Forwarded-For: 198.51.100.1, 203.0.113.7, 10.0.0.9\r\n";
+    assert_eq!(get_with(addr, "/now", spoofed).status, 429);
+    assert_eq!(get_with(addr, "/now", "X-Forwarded-For: 198.51.100.1, 10.0.0.9\r\n").status, 200);
+    assert_eq!(held.join().unwrap(), 200);
+}
+
+#[test]
+fn concurrency_limiter_log_only_reports_and_lets_requests_through() {
+    use std::sync::Mutex;
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&reports);
+    let limiter = ConcurrencyLimiter::new(1)
+        .log_only(true)
+        .with_sink(move |line| sink.lock().unwrap().push(line.to_string()));
+    let (addr, wait_for_hold) = concurrency_limited(limiter);
+
+    let held = std::thread::spawn(move || get(addr, "/hold").status);
+    wait_for_hold();
+    assert!(reports.lock().unwrap().is_empty());
+    assert_eq!(get(addr, "/now").status, 200);
+    assert_eq!(held.join().unwrap(), 200);
+    assert_eq!(*reports.lock().unwrap(), ["Concurrency limit of 1 exceeded for 127.0.0.1"]);
+}
+
+#[test]
+fn recorder_keeps_matched_requests() {
+    let recorder = RequestRecorder::new(100);
+    let mut app = Server::new();
+    app.middleware(recorder.clone());
+    app.get("/users", |_req| async { Response::text("users") });
+    app.post("/users", |_req| async { Response::text("created") });
+    app.get("/_debug/requests", recorder.endpoint());
+    let addr = serve(app);
+
+    get(addr, "/users?page=2");
+    send(addr, "POST /users HTTP/1.1\r\nContent-Length: 15\r\nConnection: close\r\n\r\n{\"name\":\"bob\"}\n");
+    get(addr, "/missing");
+
+    let recorded: serde_json::Value = serde_json::from_slice(&get(addr, "/_debug/requests").body).unwrap();
+    let recorded = recorded.as_array().unwrap();
+    assert_eq!(recorded.len(), 2);
+    assert_eq!(recorded[0]["method"], "GET");
+    assert_eq!(recorded[0]["query"], "page=2");
+    assert_eq!(recorded[1]["method"], "POST");
+    assert_eq!(recorded[1]["body"], "{\"name\":\"bob\"}\n");
+    assert_eq!(recorded[1]["status"], 200);
+}
+
+fn rate_limited(config: RateLimitConfig) -> std::net::SocketAddr {
+    let mut app = Server::new();
+    app.middleware(RateLimiter::new(config));
+    app.get("/api", |_req| async { Response::text("ok") });
+    serve(app)
+}
+
+#[test]
+fn token_bucket_allows_a_burst_then_refills() {
+    let addr = rate_limited(RateLimitConfig {
+        requests_per_minute: 120,
+        burst_size: 3,
+        algorithm: RateLimitAlgorithm::TokenBucket,
+    });
+
+    // The full bucket allows a burst of 3
+    let burst: Vec<u16> = (0..4).map(|_| get(addr, "/api").status).collect();
+    assert_eq!(burst, [200, 200, 200, 429]);
+
+    // Then one token is refilled every 500ms
+    std::thread::sleep(Duration::from_millis(600));
+    assert_eq!(get(addr, "/api").status, 200);
+    assert_eq!(get(addr, "/api").status, 429);
+}
+
+#[test]
+fn each_rate_limiter_counts_separately() {
+    let first = rate_limited(RateLimitConfig {
+        burst_size: 2,
+        ..RateLimitConfig::default()
+    });
+    let second = rate_limited(RateLimitConfig {
+        burst_size: 3,
+        ..RateLimitConfig::default()
+    });
+
+    let first: Vec<u16> = (0..3).map(|_| get(first, "/api").status).collect();
+    assert_eq!(first, [200, 200, 429]);
+    let second: Vec<u16> = (0..3).map(|_| get(second, "/api").status).collect();
+    assert_eq!(second, [200, 200, 200]);
+}
+
+#[test]
+fn forwarding_headers_do_not_reset_the_rate_limit() {
+    let addr = rate_limited(RateLimitConfig {
+        burst_size: 2,
+        ..RateLimitConfig::default()
+    });
+
+    let statuses: Vec<u16> = (1..=4)
+        .map(|i| get_with(addr, "/api", &format!("X-Forwarded-For: 198.51.100.{}\r\n", i)).status)
+        .collect();
+    assert_eq!(statuses, [200, 200, 429, 429]);
+}
+
+#[test]
+fn rate_limiter_behind_a_trusted_proxy_counts_forwarded_clients() {
+    let mut app = Server::new();
+    app.middleware(
+        RateLimiter::new(RateLimitConfig {
+            burst_size: 1,
+            ..RateLimitConfig::default()
+        })
+        .trust_proxies(["127.0.0.1".parse::<IpAddr>().unwrap()]),
+    );
+    app.get("/api", |_req| async { Response::text("ok") });
+    let addr = serve(app);
+
+    let from = |client: &str| get_with(addr, "/api", &format!("X-Forwarded-For: {}\r\n", client)).status;
+    assert_eq!(from("203.0.113.7"), 200);
+    assert_eq!(from("203.0.113.7"), 429);
+    assert_eq!(from("198.51.100.1, 203.0.113.7"), 429);
+    assert_eq!(from("198.51.100.1"), 200);
+}
+
+#[test]
+fn retry_after_matches_the_algorithm() {
+    let retry_after = |algorithm| {
+        let addr = rate_limited(RateLimitConfig {
+            requests_per_minute: 6,
+            burst_size: 1,
+            algorithm,
+        });
+        assert_eq!(get(addr, "/api").status, 200);
+        let limited = get(addr, "/api");
+        assert_eq!(limited.status, 429);
+        limited.header("Retry-After").unwrap().parse::<u64>().unwrap()
+    };
+
+    // The single request in the window expires after a minute
+    assert!((59..=60).contains(&retry_after(RateLimitAlgorithm::SlidingWindow)));
+    // At 6 requests per minute a token is refilled every 10 seconds
+    assert!((9..=10).contains(&retry_after(RateLimitAlgorithm::TokenBucket)));
+}
+
+#[test]
+fn response_size_limit_fails_oversized_bodies() {
+    let mut app = Server::new();
+    app.middleware(ResponseSizeLimit::new(1024).buffer_streams(true));
+    app.get("/small", |_req| async { Response::text("ok") });
+    app.get("/large", |_req| async { Response::text("x".repeat(4096)) });
+    app.get("/export", |_req| async {
+        let csv: &'static [u8] = &[b'x'; 4096];
+        Response::from_reader(csv, "text/csv")
+    });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/small").status, 200);
+    assert_eq!(get(addr, "/large").status, 500);
+    assert_eq!(get(addr, "/export").status, 500);
+}
+
+#[test]
+fn middleware_if_skips_static_assets_but_guards_api_routes() {
+    use axeon::middleware::BasicAuthMiddleware;
+
+    let dir = std::env::temp_dir().join(format!("axeon-middleware-if-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("assets")).unwrap();
+    std::fs::write(dir.join("assets/app.css"), "body {}").unwrap();
+
+    let mut app = Server::new();
+    app.static_dir(dir.to_str().unwrap());
+    app.middleware(Tag("all"));
+    app.middleware_if(|path| !path.starts_with("/assets/"), BasicAuthMiddleware::single_user("admin", "secret"));
+    app.get("/api/me", |_req| async { Response::text("me") });
+    let addr = serve(app);
+
+    let asset = get(addr, "/assets/app.css");
+    assert_eq!(asset.status, 200);
+    assert_eq!(asset.text(), "body {}");
+    assert_eq!(asset.headers_all("X-Ran"), ["all"]);
+
+    assert_eq!(get(addr, "/api/me").status, 401);
+    let me = get_with(addr, "/api/me", "Authorization: Basic YWRtaW46c2VjcmV0\r\n");
+    assert_eq!(me.status, 200);
+    assert_eq!(me.headers_all("X-Ran"), ["all"]);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn headers_set_inside_compression_survive_with_the_compressed_length() {
+    use axeon::middleware::{CompressionConfig, CompressionMiddleware};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[derive(Clone)]
+    struct RequestId;
+
+    impl Middleware for RequestId {
+        fn call(&self, req: Request, next: Next) -> MiddlewareResult {
+            Box::pin(async move {
+                let mut response = next.handle(req).await?;
+                response.header("X-Request-Id", "42").header("Vary", "Origin");
+                // Stale by the time compression has run
+                response.header("Content-Length", "6000");
+                Ok(response)
+            })
+        }
+
+        fn clone_box(&self) -> Box<dyn Middleware> {
+            Box::new(self.clone())
+        }
+    }
+
+    let mut app = Server::new();
+    app.middleware(CompressionMiddleware::new(CompressionConfig::default()));
+    app.middleware(RequestId);
+    app.get("/text", |_req| async { Response::text("hello ".repeat(1000)) });
+    let addr = serve(app);
+
+    let response = get_with(addr, "/text", "Accept-Encoding: gzip\r\n");
+    assert_eq!(response.header("Content-Encoding"), Some("gzip"));
+    assert_eq!(response.header("X-Request-Id"), Some("42"));
+    assert_eq!(response.header("Vary"), Some("Origin, Accept-Encoding"));
+    assert_eq!(response.headers_all("Content-Length"), [response.body.len().to_string()]);
+    let mut decoded = String::new();
+    GzDecoder::new(&response.body[..]).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, "hello ".repeat(1000));
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn streamed_bodies_are_compressed_as_they_go() {
+    use axeon::middleware::{CompressionConfig, CompressionMiddleware};
+    use flate2::read::{DeflateDecoder, GzDecoder};
+    use std::io::Read;
+
+    let mut app = Server::new();
+    app.middleware(CompressionMiddleware::new(CompressionConfig::default()));
+    app.get("/rows", |_req| async {
+        let rows = (0..200).map(|id| Ok(format!("{},user{}\n", id, id).into_bytes()));
+        Response::from_stream(futures::stream::iter(rows), "text/csv")
+    });
+    let addr = serve(app);
+    let expected: String = (0..200).map(|id| format!("{},user{}\n", id, id)).collect();
+
+    let gzip = get_with(addr, "/rows", "Accept-Encoding: gzip\r\n");
+    assert_eq!(gzip.header("Content-Encoding"), Some("gzip"));
+    assert_eq!(gzip.header("Transfer-Encoding"), Some("chunked"));
+    let mut decoded = String::new();
+    GzDecoder::new(&gzip.body[..]).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, expected);
+
+    let deflate = get_with(addr, "/rows", "Accept-Encoding: deflate\r\n");
+    assert_eq!(deflate.header("Content-Encoding"), Some("deflate"));
+    let mut decoded = String::new();
+    DeflateDecoder::new(&deflate.body[..]).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, expected);
+
+    assert_eq!(get(addr, "/rows").text(), expected);
+}
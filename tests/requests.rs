@@ -0,0 +1,498 @@
+mod common;
+
+use axeon::{Method, Request, Response, Server};
+use common::{get, get_with, read_response, send, send_raw, serve};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+#[test]
+fn max_body_size_accepts_the_limit_and_refuses_more() {
+    let mut app = Server::new();
+    app.max_body_size(1024);
+    app.post("/upload", |req| async move { Response::text(req.body.as_bytes().len().to_string()) });
+    let addr = serve(app);
+
+    // A body right at the limit is accepted
+    let mut request = b"POST /upload HTTP/1.1\r\nContent-Length: 1024\r\nConnection: close\r\n\r\n".to_vec();
+    request.extend_from_slice(&[b'x'; 1024]);
+    let raw = String::from_utf8(send_raw(addr, &request)).unwrap();
+    assert!(raw.starts_with("HTTP/1.1 200"));
+    assert!(raw.ends_with("1024"));
+
+    // A declared length over it is refused without reading the body
+    let response = send(addr, "POST /upload HTTP/1.1\r\nContent-Length: 4000000000\r\n\r\n");
+    assert_eq!(response.status, 413);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn max_body_size_applies_to_the_decoded_body() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let mut app = Server::new();
+    app.max_body_size(1024);
+    app.post("/upload", |req| async move { Response::text(req.body.as_string()) });
+    let addr = serve(app);
+
+    // 4 KiB of zeros compresses far below the limit but inflates past it
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&[0u8; 4096]).unwrap();
+    let body = encoder.finish().unwrap();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let head = format!(
+        "POST /upload HTTP/1.1\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nExpect: 100-continue\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).unwrap();
+
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    assert_eq!(read_response(&mut reader).status, 100);
+    stream.write_all(&body).unwrap();
+    assert_eq!(read_response(&mut reader).status, 413);
+}
+
+#[test]
+fn malformed_content_length_is_400_and_closes_the_connection() {
+    let mut app = Server::new();
+    app.post("/upload", |req| async move { Response::text(req.body.as_string()) });
+    let addr = serve(app);
+
+    for length in ["five", "-1", "+5", "1, 2", ""] {
+        // Were the length skipped, the smuggled request would get a reply too
+        let raw = format!(
+            "POST /upload HTTP/1.1\r\nContent-Length: {}\r\n\r\nGET /upload HTTP/1.1\r\n\r\n",
+            length
+        );
+        let raw = String::from_utf8(send_raw(addr, raw.as_bytes())).unwrap();
+        assert!(raw.starts_with("HTTP/1.1 400"), "{:?} got {}", length, raw);
+        assert_eq!(raw.matches("HTTP/1.1 ").count(), 1, "{:?} got a second response", length);
+    }
+
+    let conflicting = "POST /upload HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 3\r\n\r\nabc";
+    assert_eq!(send(addr, conflicting).status, 400);
+
+    // Repeats of the same length are one length
+    let repeated = "POST /upload HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 2, 2\r\nConnection: close\r\n\r\nhi";
+    assert_eq!(send(addr, repeated).text(), "hi");
+}
+
+#[test]
+fn content_length_with_transfer_encoding_is_400_and_closes_the_connection() {
+    let mut app = Server::new();
+    app.post("/upload", |req| async move { Response::text(req.body.as_string()) });
+    let addr = serve(app);
+
+    for transfer_encoding in ["chunked", "Chunked", "gzip, chunked"] {
+        // Either framing would leave the other request smuggled in the body
+        let raw = format!(
+            "POST /upload HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: {}\r\n\r\n0\r\n\r\nGET /upload HTTP/1.1\r\n\r\n",
+            transfer_encoding
+        );
+        let raw = String::from_utf8(send_raw(addr, raw.as_bytes())).unwrap();
+        assert!(raw.starts_with("HTTP/1.1 400"), "{:?} got {}", transfer_encoding, raw);
+        assert!(raw.contains("Connection: close"));
+        assert_eq!(raw.matches("HTTP/1.1 ").count(), 1, "{:?} got a second response", transfer_encoding);
+    }
+}
+
+#[test]
+fn chunked_bodies_are_decoded_and_the_connection_reused() {
+    let mut app = Server::new();
+    app.post("/echo", |req| async move { Response::text(req.body.as_string()) });
+    let addr = serve(app);
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(
+            b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+              5\r\nhello\r\n7;note=ext\r\n, world\r\n0\r\nX-Checksum: 1\r\n\r\n\
+              POST /echo HTTP/1.1\r\nContent-Length: 4\r\nConnection: close\r\n\r\nnext",
+        )
+        .unwrap();
+    let mut reader = BufReader::new(stream);
+    let first = read_response(&mut reader);
+    assert_eq!((first.status, first.text().as_str()), (200, "hello, world"));
+    let second = read_response(&mut reader);
+    assert_eq!((second.status, second.text().as_str()), (200, "next"));
+}
+
+#[test]
+fn oversized_chunk_lines_and_too_many_trailers_are_400() {
+    let mut app = Server::new();
+    app.post("/echo", |req| async move { Response::text(req.body.as_string()) });
+    let addr = serve(app);
+    let post = |chunks: &str| {
+        send(addr, &format!("POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{}", chunks))
+    };
+
+    let long_extension = format!("5;note={}\r\nhello\r\n0\r\n\r\n", "x".repeat(5000));
+    assert_eq!(post(&long_extension).status, 400);
+    let long_trailer = format!("5\r\nhello\r\n0\r\nX-Note: {}\r\n\r\n", "x".repeat(5000));
+    assert_eq!(post(&long_trailer).status, 400);
+    let many_trailers = format!("5\r\nhello\r\n0\r\n{}\r\n", "X-Note: 1\r\n".repeat(33));
+    assert_eq!(post(&many_trailers).status, 400);
+
+    let some_trailers = format!("5\r\nhello\r\n0\r\n{}\r\n", "X-Note: 1\r\n".repeat(32));
+    assert_eq!(post(&some_trailers).text(), "hello");
+}
+
+#[test]
+fn method_safety_and_idempotency() {
+    let methods = [
+        (Method::GET, true, true),
+        (Method::HEAD, true, true),
+        (Method::OPTIONS, true, true),
+        (Method::TRACE, true, true),
+        (Method::PUT, false, true),
+        (Method::DELETE, false, true),
+        (Method::POST, false, false),
+        (Method::PATCH, false, false),
+        (Method::CONNECT, false, false),
+    ];
+    for (method, safe, idempotent) in methods {
+        assert_eq!(method.is_safe(), safe, "{:?}", method);
+        assert_eq!(method.is_idempotent(), idempotent, "{:?}", method);
+    }
+}
+
+#[test]
+fn params_are_percent_decoded_after_matching() {
+    let mut app = Server::new();
+    app.get("/files/:name", |req| async move { Response::text(format!("file {}", req.params["name"])) });
+    app.get("/files/:dir/:name", |req| async move { Response::text(format!("dir {}", req.params["dir"])) });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/files/a%2Fb").text(), "file a/b");
+    assert_eq!(get(addr, "/files/a/b").text(), "dir a");
+    assert_eq!(get(addr, "/files/my%20notes.txt").text(), "file my notes.txt");
+}
+
+#[test]
+fn raw_query_keeps_the_original_encoding_and_order() {
+    let mut app = Server::new();
+    app.get("/sign", |req| async move { Response::text(format!("{} {}", req.raw_query(), req.query["b"])) });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/sign?z=1&b=a%20b%2Bc&a=%7E").text(), "z=1&b=a%20b%2Bc&a=%7E a b+c");
+    assert_eq!(get(addr, "/sign?b=x&b=y").text(), "b=x&b=y y");
+}
+
+#[test]
+fn remote_addr_is_the_peer_address() {
+    let mut app = Server::new();
+    app.get("/", |req| async move {
+        Response::text(req.remote_addr.map(|addr| addr.ip().to_string()).unwrap_or_default())
+    });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/").text(), "127.0.0.1");
+}
+
+#[test]
+fn query_typed_deserializes_the_query_string() {
+    #[derive(Deserialize)]
+    struct Pagination {
+        page: u32,
+        limit: u32,
+        tags: Option<Vec<String>>,
+    }
+
+    let mut app = Server::new();
+    app.get("/items", |req| async move {
+        let Some(pagination) = req.query_typed::<Pagination>() else {
+            return Response::text("invalid");
+        };
+        let tags = pagination.tags.unwrap_or_default().join(",");
+        let page: Option<u32> = req.query_param("page");
+        Response::text(format!("{} {} {} {:?}", pagination.page, pagination.limit, tags, page))
+    });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/items?page=2&limit=50").text(), "2 50  Some(2)");
+    assert_eq!(get(addr, "/items?page=3&limit=10&tags[]=new&tags[]=sale").text(), "3 10 new,sale Some(3)");
+    assert_eq!(get(addr, "/items?page=two&limit=10").text(), "invalid");
+}
+
+#[test]
+fn param_parse_failures_are_400() {
+    let mut app = Server::new();
+    app.get("/users/:id", |req| async move {
+        let id: u64 = req.param("id")?;
+        Response::text(format!("user {}", id))
+    });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/users/42").text(), "user 42");
+    let invalid = get(addr, "/users/abc");
+    assert_eq!(invalid.status, 400);
+    assert!(invalid.text().contains("Invalid path parameter 'id'"));
+}
+
+#[test]
+fn content_length_reports_the_body_length() {
+    let mut app = Server::new();
+    app.post("/upload", |req| async move { Response::text(format!("{:?}", req.content_length())) });
+    let addr = serve(app);
+
+    let post = |headers: &str, body: &str| {
+        send(addr, &format!("POST /upload HTTP/1.1\r\n{}Connection: close\r\n\r\n{}", headers, body))
+    };
+    assert_eq!(post("Content-Length: 5\r\n", "hello").text(), "Some(5)");
+    assert_eq!(post("", "").text(), "None");
+    assert_eq!(post("Content-Length: five\r\n", "").status, 400);
+    assert_eq!(post("Transfer-Encoding: chunked\r\n", "3\r\nabc\r\n0\r\n\r\n").text(), "Some(3)");
+}
+
+#[test]
+fn get_header_all_returns_repeated_headers_in_order() {
+    let mut app = Server::new();
+    app.get("/", |req| async move { Response::text(req.get_header_all("X-Forwarded-For").join(" | ")) });
+    let addr = serve(app);
+
+    let response = get_with(addr, "/", "X-Forwarded-For: 203.0.113.7\r\nX-Forwarded-For: 10.0.0.1, 10.0.0.2\r\n");
+    assert_eq!(response.text(), "203.0.113.7 | 10.0.0.1, 10.0.0.2");
+}
+
+#[test]
+fn early_hints_are_sent_before_the_response() {
+    let mut app = Server::new();
+    app.get("/", |req| async move {
+        req.early_hints(&["</app.css>; rel=preload; as=style"]);
+        Response::html("<link rel=stylesheet href=/app.css>")
+    });
+    let addr = serve(app);
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    let mut reader = BufReader::new(stream);
+    let hints = read_response(&mut reader);
+    assert_eq!(hints.status, 103);
+    assert_eq!(hints.header("Link"), Some("</app.css>; rel=preload; as=style"));
+    assert_eq!(read_response(&mut reader).status, 200);
+
+    let mut rest = String::new();
+    assert_eq!(reader.read_line(&mut rest).unwrap(), 0);
+}
+
+#[test]
+fn route_timeouts_are_enforced_per_route() {
+    let mut app = Server::new();
+    // Set before the route exists, which must still apply
+    app.route_timeout(Method::GET, "/report", Duration::from_millis(200));
+    let sleep_then_answer = |req: Request| async move {
+        let ms: u64 = req.param("ms")?;
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        Response::text("done")
+    };
+    app.get("/report", |_req| async {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Response::text("done")
+    });
+    app.get("/sleep/:ms", sleep_then_answer);
+    app.route_timeout(Method::GET, "/sleep/:ms", Duration::from_millis(600));
+    let addr = serve(app);
+
+    let started = Instant::now();
+    assert_eq!(get(addr, "/report").status, 504);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(get(addr, "/sleep/300").status, 200);
+    assert_eq!(get(addr, "/sleep/2000").status, 504);
+}
+
+#[test]
+fn deadline_follows_the_route_timeout() {
+    let mut app = Server::new();
+    app.request_timeout(Duration::from_secs(5));
+    let remaining = |req: Request| async move {
+        let left = req.deadline().unwrap().saturating_duration_since(Instant::now());
+        Response::text(left.as_millis().to_string())
+    };
+    app.get("/fast", remaining);
+    app.get("/slow", remaining);
+    app.route_timeout(Method::GET, "/slow", Duration::from_secs(60));
+    let addr = serve(app);
+
+    let remaining_ms = |path: &str| -> u64 { get(addr, path).text().parse().unwrap() };
+    let fast = remaining_ms("/fast");
+    assert!(fast > 0 && fast <= 5_000);
+    let slow = remaining_ms("/slow");
+    assert!(slow > 5_000 && slow <= 60_000);
+}
+
+#[test]
+fn request_timeout_is_separate_from_the_idle_timeout() {
+    let mut app = Server::new();
+    app.keep_alive(Duration::from_millis(300))
+        .request_timeout(Duration::from_millis(800));
+    app.get("/slow", |_req| async {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Response::text("done")
+    });
+    app.get("/fast", |_req| async { Response::text("done") });
+    let addr = serve(app);
+
+    // An idle connection is closed after the keep-alive timeout, unanswered
+    let started = Instant::now();
+    let mut idle = TcpStream::connect(addr).unwrap();
+    let mut rest = Vec::new();
+    std::io::Read::read_to_end(&mut idle, &mut rest).unwrap();
+    assert!(rest.is_empty());
+    assert!(started.elapsed() < Duration::from_millis(800));
+
+    // Headers sent slower than the idle timeout still get an answer, as
+    // long as the whole request fits in the request timeout
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /fast HTTP/1.1\r\n").unwrap();
+    std::thread::sleep(Duration::from_millis(400));
+    stream.write_all(b"Connection: close\r\n\r\n").unwrap();
+    assert_eq!(read_response(&mut BufReader::new(stream)).status, 200);
+
+    // Headers that never finish, or a slow handler, run into it
+    let mut stalled = TcpStream::connect(addr).unwrap();
+    stalled.write_all(b"GET /fast HTTP/1.1\r\n").unwrap();
+    assert_eq!(read_response(&mut BufReader::new(stalled)).status, 504);
+
+    let started = Instant::now();
+    assert_eq!(get(addr, "/slow").status, 504);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+// Answers with the `name` field of a JSON body, or 400 if it isn't JSON
+fn json_echo(app: &mut Server) {
+    app.post("/echo", |req| async move {
+        match req.body.json_value() {
+            Some(value) => Response::text(value["name"].as_str().unwrap_or_default()),
+            None => Err(axeon::ServerError::BadRequest("not JSON".to_string())),
+        }
+    });
+}
+
+fn post_json(addr: std::net::SocketAddr, headers: &str, body: &str) -> common::HttpResponse {
+    send(
+        addr,
+        &format!("POST /echo HTTP/1.1\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}", headers, body.len(), body),
+    )
+}
+
+#[test]
+fn json_bodies_are_parsed_for_json_content_types_only() {
+    let mut app = Server::new();
+    json_echo(&mut app);
+    let addr = serve(app);
+    let body = r#"{"name":"ada"}"#;
+
+    for content_type in [
+        "application/json",
+        "application/json; charset=utf-8",
+        "text/json",
+        "application/vnd.api+json",
+        "application/merge-patch+json",
+    ] {
+        let response = post_json(addr, &format!("Content-Type: {}\r\n", content_type), body);
+        assert_eq!((response.status, response.text().as_str()), (200, "ada"), "{}", content_type);
+    }
+    for content_type in ["text/plain", "application/x-www-form-urlencoded", "application/jsonx"] {
+        let response = post_json(addr, &format!("Content-Type: {}\r\n", content_type), body);
+        assert_eq!(response.status, 400, "{}", content_type);
+    }
+    assert_eq!(post_json(addr, "", body).status, 400);
+}
+
+#[test]
+fn bodies_without_a_content_type_use_the_default_or_sniffing() {
+    let body = r#"{"name":"ada"}"#;
+
+    let mut defaulted = Server::new();
+    defaulted.default_content_type("application/json");
+    json_echo(&mut defaulted);
+    let addr = serve(defaulted);
+    assert_eq!(post_json(addr, "", body).text(), "ada");
+    // An explicit Content-Type still wins over the default
+    assert_eq!(post_json(addr, "Content-Type: text/plain\r\n", body).status, 400);
+
+    let mut sniffing = Server::new();
+    sniffing.sniff_content_type(true);
+    json_echo(&mut sniffing);
+    let addr = serve(sniffing);
+    assert_eq!(post_json(addr, "", body).text(), "ada");
+    assert_eq!(post_json(addr, "", "name=ada").status, 400);
+}
+
+#[test]
+fn is_ajax_and_wants_json_read_the_request_headers() {
+    let mut app = Server::new();
+    app.get("/", |req| async move { Response::text(format!("{} {}", req.is_ajax(), req.wants_json())) });
+    let addr = serve(app);
+    let check = |headers: &str| get_with(addr, "/", headers).text();
+
+    assert_eq!(check(""), "false false");
+    assert_eq!(check("X-Requested-With: XMLHttpRequest\r\n"), "true false");
+    assert_eq!(check("X-Requested-With: xmlhttprequest\r\nAccept: application/json\r\n"), "true true");
+    assert_eq!(check("Accept: application/problem+json\r\n"), "false true");
+    assert_eq!(check("Accept: text/html, application/json;q=0.9\r\n"), "false false");
+    assert_eq!(check("Accept: text/html;q=0.5, application/json\r\n"), "false true");
+    assert_eq!(check("Accept: application/json;q=0\r\n"), "false false");
+    assert_eq!(check("Accept: */*\r\n"), "false false");
+}
+
+// Answers with the parsed query as a sorted `{key: value}` debug string
+fn parsed_query(target: &str) -> String {
+    let mut app = Server::new();
+    app.get("/search", |req| async move {
+        let query: std::collections::BTreeMap<_, _> = req.query.into_iter().collect();
+        Response::text(format!("{:?}", query))
+    });
+    get(serve(app), &format!("/search{}", target)).text()
+}
+
+#[test]
+fn query_flags_and_empty_values_are_kept_and_empty_keys_dropped() {
+    assert_eq!(parsed_query("?flag"), r#"{"flag": ""}"#);
+    assert_eq!(parsed_query("?a=&b=2"), r#"{"a": "", "b": "2"}"#);
+    assert_eq!(parsed_query("?=x"), "{}");
+    assert_eq!(parsed_query("?=x&debug&&token=a=b"), r#"{"debug": "", "token": "a=b"}"#);
+    assert_eq!(parsed_query(""), "{}");
+}
+
+#[test]
+fn query_keys_and_values_are_percent_decoded() {
+    assert_eq!(parsed_query("?q=hello%20world"), r#"{"q": "hello world"}"#);
+    assert_eq!(parsed_query("?q=hello+world"), r#"{"q": "hello world"}"#);
+    assert_eq!(parsed_query("?q=a%2Bb"), r#"{"q": "a+b"}"#);
+    // Decoded after splitting, so encoded separators stay in the value
+    assert_eq!(parsed_query("?q=fish%26chips&eq=1%3D1"), r#"{"eq": "1=1", "q": "fish&chips"}"#);
+    assert_eq!(parsed_query("?first%20name=ada&tag"), r#"{"first name": "ada", "tag": ""}"#);
+    assert_eq!(parsed_query("?city=K%C3%B6ln"), r#"{"city": "Köln"}"#);
+}
+
+#[test]
+fn cookies_are_parsed_from_the_cookie_header() {
+    let mut app = Server::new();
+    app.get("/", |req| async move {
+        let cookies: std::collections::BTreeMap<_, _> = req.cookies().into_iter().collect();
+        Response::text(format!("{:?} {:?}", cookies, req.cookie("theme")))
+    });
+    let addr = serve(app);
+    let cookies = |header: &str| get_with(addr, "/", &format!("Cookie: {}\r\n", header)).text();
+
+    assert_eq!(cookies("session=abc; theme=dark"), r#"{"session": "abc", "theme": "dark"} Some("dark")"#);
+    assert_eq!(cookies(r#"theme="dark mode";id=7"#), r#"{"id": "7", "theme": "dark mode"} Some("dark mode")"#);
+    assert_eq!(cookies("flag; =x; token=a=b"), r#"{"token": "a=b"} None"#);
+    assert_eq!(get(addr, "/").text(), "{} None");
+}
+
+#[test]
+fn json_bodies_up_to_max_body_size_are_parsed() {
+    let mut app = Server::new();
+    app.max_body_size(16 * 1024 * 1024);
+    json_echo(&mut app);
+    let addr = serve(app);
+
+    let body = format!(r#"{{"name":"ada","padding":"{}"}}"#, "x".repeat(3 * 1024 * 1024));
+    let response = post_json(addr, "Content-Type: application/json\r\n", &body);
+    assert_eq!((response.status, response.text().as_str()), (200, "ada"));
+}
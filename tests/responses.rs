@@ -0,0 +1,292 @@
+mod common;
+
+use axeon::{json, json_handler, CookieOptions, Handler, Json, Request, Response, Server, ServerError, SseEvent};
+use common::{get, get_with, read_response, send, send_raw, serve};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn map_response_adjusts_the_handler_response() {
+    async fn checkout(_req: Request) -> Result<Response, ServerError> {
+        Response::text("checkout")
+    }
+
+    let mut app = Server::new();
+    app.get(
+        "/checkout",
+        checkout.map_response(|mut response| {
+            response.header("X-Variant", "b");
+            response
+        }),
+    );
+    let addr = serve(app);
+
+    let response = get(addr, "/checkout");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("X-Variant"), Some("b"));
+    assert_eq!(response.text(), "checkout");
+}
+
+#[test]
+fn json_values_are_responses_and_json_handlers_round_trip() {
+    #[derive(Deserialize)]
+    struct Greet {
+        name: String,
+    }
+
+    #[derive(Serialize)]
+    struct Greeting {
+        message: String,
+    }
+
+    let mut app = Server::new();
+    app.get("/version", |_req| Json(json!({ "version": "0.2.0" })));
+    app.post("/greet", json_handler(|Json(greet): Json<Greet>| async move {
+        Json(Greeting { message: format!("Hello, {}!", greet.name) })
+    }));
+    let addr = serve(app);
+
+    let version = get(addr, "/version");
+    assert_eq!(version.status, 200);
+    assert_eq!(version.header("Content-Type"), Some("application/json"));
+    assert_eq!(version.text(), r#"{"version":"0.2.0"}"#);
+
+    let post = |body: &str| {
+        send(addr, &format!("POST /greet HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body))
+    };
+    let greeting = post(r#"{"name":"Ada"}"#);
+    assert_eq!(greeting.status, 200);
+    assert_eq!(greeting.text(), r#"{"message":"Hello, Ada!"}"#);
+    assert_eq!(post(r#"{"nom":"Ada"}"#).status, 400);
+}
+
+#[test]
+fn panics_get_a_plain_500_and_the_server_keeps_serving() {
+    let (sender, panics) = std::sync::mpsc::channel();
+    let sender = std::sync::Mutex::new(sender);
+    let mut app = Server::new();
+    app.on_panic(move |msg| sender.lock().unwrap().send(msg.to_string()).unwrap());
+    app.get("/boom", |_req| async {
+        if true {
+            panic!("database password is hunter2");
+        }
+        Response::text("unreachable")
+    });
+    app.get("/ok", |_req| async { Response::text("still here") });
+    let addr = serve(app);
+
+    // Same connection: the panic doesn't take the connection down either
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET /boom HTTP/1.1\r\n\r\nGET /ok HTTP/1.1\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut reader = BufReader::new(stream);
+    let boom = read_response(&mut reader);
+    assert_eq!(boom.status, 500);
+    assert_eq!(boom.header("Content-Type"), Some("text/plain"));
+    assert_eq!(boom.text(), "Internal Server Error");
+    assert_eq!(read_response(&mut reader).text(), "still here");
+
+    assert_eq!(panics.recv_timeout(Duration::from_secs(5)).unwrap(), "database password is hunter2");
+    assert_eq!(get(addr, "/ok").text(), "still here");
+}
+
+#[test]
+fn error_variants_carry_their_challenge_and_retry_delay() {
+    let mut app = Server::new();
+    app.get("/private", |_req| async {
+        Err::<Response, _>(ServerError::Unauthorized {
+            message: "Token expired".to_string(),
+            challenge: "Bearer error=\"invalid_token\"".to_string(),
+        })
+    });
+    app.get("/busy", |_req| async {
+        Err::<Response, _>(ServerError::TooManyRequests { retry_after: Some(Duration::from_secs(5)) })
+    });
+    app.get("/crowded", |_req| async { Err::<Response, _>(ServerError::TooManyRequests { retry_after: None }) });
+    let addr = serve(app);
+
+    let private = get(addr, "/private");
+    assert_eq!(private.status, 401);
+    assert_eq!(private.header("WWW-Authenticate"), Some("Bearer error=\"invalid_token\""));
+    let busy = get(addr, "/busy");
+    assert_eq!(busy.status, 429);
+    assert_eq!(busy.header("Retry-After"), Some("5"));
+    let crowded = get(addr, "/crowded");
+    assert_eq!(crowded.status, 429);
+    assert_eq!(crowded.header("Retry-After"), None);
+}
+
+#[test]
+fn appended_set_cookie_headers_are_sent_separately() {
+    let mut app = Server::new();
+    app.get("/", |_req| async {
+        let mut response = Response::text("hi")?;
+        response.append_header("Set-Cookie", "a=1").append_header("Set-Cookie", "b=2");
+        Ok(response)
+    });
+    app.get("/replaced", |_req| async {
+        let mut response = Response::text("hi")?;
+        response.set_cookie("a", "1", CookieOptions::default()).set_header("set-cookie", "b=2");
+        assert_eq!(response.get_header("Set-Cookie"), Some("b=2"));
+        Ok(response)
+    });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/").headers_all("Set-Cookie"), ["a=1", "b=2"]);
+    assert_eq!(get(addr, "/replaced").headers_all("Set-Cookie"), ["b=2"]);
+}
+
+#[test]
+fn from_stream_sends_each_item_as_a_chunk() {
+    let mut app = Server::new();
+    app.get("/rows", |_req| async {
+        let rows = ["id,name\n", "1,alice\n", "2,bob\n"];
+        let chunks = futures::stream::iter(rows.map(|row| Ok(row.as_bytes().to_vec())));
+        Response::from_stream(chunks, "text/csv")
+    });
+    let addr = serve(app);
+
+    let raw = send_raw(addr, b"GET /rows HTTP/1.1\r\nConnection: close\r\n\r\n");
+    let raw = String::from_utf8(raw).unwrap();
+    assert!(raw.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(raw.ends_with("\r\n\r\n8\r\nid,name\n\r\n8\r\n1,alice\n\r\n6\r\n2,bob\n\r\n0\r\n\r\n"));
+}
+
+fn event_stream_lines(addr: std::net::SocketAddr, path: &str) -> Vec<String> {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nConnection: close\r\n\r\n", path).unwrap();
+    BufReader::new(stream).lines().map(|line| line.unwrap()).collect()
+}
+
+#[test]
+fn sse_streams_events_as_they_are_produced() {
+    let mut app = Server::new();
+    app.get("/ticks", |_req| async {
+        let ticks = futures::stream::unfold(1, |n| async move {
+            if n > 2 {
+                return None;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Some((SseEvent::new(format!("tick {}", n)).event("tick").id(n.to_string()), n + 1))
+        });
+        Response::sse(ticks)
+    });
+    let addr = serve(app);
+
+    let lines = event_stream_lines(addr, "/ticks");
+    assert!(lines.contains(&"Content-Type: text/event-stream".to_string()));
+    let fields: Vec<&String> = lines
+        .iter()
+        .filter(|line| line.starts_with("event:") || line.starts_with("id:") || line.starts_with("data:"))
+        .collect();
+    assert_eq!(fields, ["event: tick", "id: 1", "data: tick 1", "event: tick", "id: 2", "data: tick 2"]);
+}
+
+#[test]
+fn sse_with_retry_sends_keep_alive_comments() {
+    let mut app = Server::new();
+    app.get("/jobs", |_req| async {
+        let done = futures::stream::once(async {
+            tokio::time::sleep(Duration::from_millis(350)).await;
+            SseEvent::new("done")
+        });
+        Response::sse_with_retry(done, Duration::from_secs(2), Duration::from_millis(100))
+    });
+    let addr = serve(app);
+
+    let lines = event_stream_lines(addr, "/jobs");
+    let frames: Vec<&String> = lines
+        .iter()
+        .filter(|line| line.starts_with("retry:") || line.starts_with(':') || line.starts_with("data:"))
+        .collect();
+    assert_eq!(frames.first().unwrap().as_str(), "retry: 2000");
+    assert_eq!(frames.last().unwrap().as_str(), "data: done");
+    let comments = frames.iter().filter(|line| line.as_str() == ":keep-alive").count();
+    assert!((2..=4).contains(&comments));
+}
+
+#[test]
+fn last_modified_answers_conditional_requests() {
+    let published = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut app = Server::new();
+    app.get("/article", move |_req| async move {
+        let mut response = Response::text("article")?;
+        response.last_modified(published);
+        Ok(response)
+    });
+    let addr = serve(app);
+
+    let since = |time: SystemTime| format!("If-Modified-Since: {}\r\n", httpdate::fmt_http_date(time));
+    let recent = since(published + Duration::from_secs(60));
+    assert_eq!(get_with(addr, "/article", &recent).status, 304);
+    let raw = send_raw(addr, format!("GET /article HTTP/1.1\r\n{}Connection: close\r\n\r\n", recent).as_bytes());
+    assert!(raw.ends_with(b"\r\n\r\n"));
+
+    let stale = since(published - Duration::from_secs(60));
+    assert_eq!(get_with(addr, "/article", &stale).text(), "article");
+}
+
+#[test]
+fn from_file_serves_the_file_with_its_content_type() {
+    let dir = std::env::temp_dir().join(format!("axeon-from-file-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("report.json"), br#"{"total":3}"#).unwrap();
+
+    let mut app = Server::new();
+    app.get("/report/:name", move |req| {
+        let path = dir.join(req.params.get("name").unwrap());
+        async move { Response::from_file(path).await }
+    });
+    let addr = serve(app);
+
+    let report = get(addr, "/report/report.json");
+    assert_eq!(report.status, 200);
+    assert_eq!(report.header("Content-Type"), Some("application/json"));
+    assert_eq!(report.body, br#"{"total":3}"#);
+
+    assert_eq!(get(addr, "/report/missing.json").status, 404);
+}
+
+#[test]
+fn no_content_and_not_modified_have_no_length_or_body() {
+    let mut app = Server::new();
+    app.delete("/items/1", |_req| async { Ok(Response::no_content()) });
+    app.get("/cached", |_req| async {
+        let mut response = Response::new(304);
+        response.header("Content-Length", "4").body("oops");
+        Ok(response)
+    });
+    let addr = serve(app);
+
+    for (method, path, status) in [("DELETE", "/items/1", "204"), ("GET", "/cached", "304")] {
+        // Followed by a second request that must still be read as a request
+        let raw = format!("{} {} HTTP/1.1\r\n\r\nGET /cached HTTP/1.1\r\nConnection: close\r\n\r\n", method, path);
+        let raw = String::from_utf8(send_raw(addr, raw.as_bytes())).unwrap();
+        let (first, second) = raw.split_at(raw.find("\r\n\r\n").unwrap() + 4);
+        assert!(first.starts_with(&format!("HTTP/1.1 {}", status)), "{}", raw);
+        assert!(!first.to_ascii_lowercase().contains("content-length"), "{}", first);
+        assert!(second.starts_with("HTTP/1.1 304"), "{}", raw);
+        assert!(second.ends_with("\r\n\r\n"));
+    }
+}
+
+#[test]
+fn from_reader_streams_the_reader_to_the_end() {
+    let mut app = Server::new();
+    app.get("/export", |_req| async {
+        // Several reads' worth, so the body spans many chunks
+        let rows: String = (0..5000).map(|id| format!("{},user{}\n", id, id)).collect();
+        Response::from_reader(std::io::Cursor::new(rows.into_bytes()), "text/csv")
+    });
+    let addr = serve(app);
+
+    let response = get(addr, "/export");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("text/csv"));
+    assert_eq!(response.header("Transfer-Encoding"), Some("chunked"));
+    let expected: String = (0..5000).map(|id| format!("{},user{}\n", id, id)).collect();
+    assert_eq!(response.text(), expected);
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use axeon::middleware::{SecurityConfig, SecurityHeaders};
+use axeon::{json, route, Method, Request, Response, Router, Server, ServerError, TrailingSlashMode};
+use common::{get, send, serve};
+
+#[test]
+fn options_lists_the_allowed_methods() {
+    let mut app = Server::new();
+    app.post("/items", |_req| async { Response::text("created") });
+    app.delete("/items", |_req| async { Response::text("deleted") });
+    let addr = serve(app);
+
+    let response = send(addr, "OPTIONS /items HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert_eq!(response.status, 204);
+    assert_eq!(response.header("Allow"), Some("POST, DELETE, OPTIONS"));
+}
+
+#[test]
+fn options_uses_the_middleware_of_the_first_method_in_order() {
+    for _ in 0..10 {
+        let mut api = Router::new();
+        api.post("/items", |_req| async { Response::text("created") });
+        // Only routes registered from here on get the middleware
+        api.middleware(SecurityHeaders::new(SecurityConfig::default()));
+        api.delete("/items", |_req| async { Response::text("deleted") });
+        api.trace("/items", |_req| async { Response::text("traced") });
+        let mut app = Server::new();
+        app.use_router(api);
+        let addr = serve(app);
+
+        let response = send(addr, "OPTIONS /items HTTP/1.1\r\nConnection: close\r\n\r\n");
+        assert_eq!(response.status, 204);
+        assert_eq!(response.header("Allow"), Some("POST, DELETE, OPTIONS, TRACE"));
+        assert_eq!(response.header("X-Content-Type-Options"), None);
+    }
+}
+
+#[test]
+fn unmatched_method_is_405_with_allow() {
+    let mut app = Server::new();
+    app.get("/users", |_req| async { Response::text("users") });
+    let addr = serve(app);
+
+    let response = send(addr, "POST /users HTTP/1.1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Allow"), Some("GET, HEAD, OPTIONS"));
+}
+
+#[test]
+fn routes_from_a_table_each_resolve() {
+    async fn list_users(_req: Request) -> Result<Response, ServerError> {
+        Response::text("users")
+    }
+
+    let mut admin = Router::new();
+    admin.register([
+        route(Method::GET, "/stats", |_req| async { Response::text("stats") }),
+        route(Method::DELETE, "/cache", |_req| async { Ok(Response::no_content()) }),
+    ]);
+    let mut app = Server::new();
+    app.register(vec![
+        route(Method::GET, "/users", list_users),
+        route(Method::POST, "/users", |_req| async { Response::created(&json!({ "id": 1 })) }),
+        route(Method::GET, "/users/:id", |req: Request| async move {
+            Response::text(format!("user {}", req.params["id"]))
+        }),
+    ]);
+    app.mount("/admin", admin);
+    let addr = serve(app);
+
+    let request = |method: &str, path: &str| {
+        send(addr, &format!("{} {} HTTP/1.1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", method, path))
+    };
+    let users = request("GET", "/users");
+    assert_eq!((users.status, users.text().as_str()), (200, "users"));
+    let created = request("POST", "/users");
+    assert_eq!((created.status, created.text().as_str()), (201, r#"{"id":1}"#));
+    let user = request("GET", "/users/7");
+    assert_eq!((user.status, user.text().as_str()), (200, "user 7"));
+    let stats = request("GET", "/admin/stats");
+    assert_eq!((stats.status, stats.text().as_str()), (200, "stats"));
+    assert_eq!(request("DELETE", "/admin/cache").status, 204);
+    assert_eq!(request("PUT", "/users").status, 405);
+}
+
+#[test]
+fn rewrite_hooks_run_in_order_before_routing() {
+    let mut app = Server::new();
+    app.rewrite(|req| {
+        if req.path == "/old" {
+            req.path = "/new".to_string();
+        }
+    });
+    app.rewrite(|req| {
+        if req.path == "/new" && req.query.contains_key("beta") {
+            req.path = "/beta".to_string();
+        }
+    });
+    app.get("/old", |_req| async { Response::text("old") });
+    app.get("/new", |req| async move { Response::text(format!("new from {}", req.raw_path())) });
+    app.get("/beta", |_req| async { Response::text("beta") });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/old").text(), "new from /old");
+    assert_eq!(get(addr, "/old?beta").text(), "beta");
+    assert_eq!(get(addr, "/new").text(), "new from /new");
+}
+
+#[test]
+fn strict_slash_routers_do_not_match_a_trailing_slash() {
+    let mut lenient = Router::new();
+    lenient.get("/users", |_req| async { Response::text("users") });
+    let mut strict = Router::new();
+    strict.strict_slash(true);
+    strict.get("/users", |_req| async { Response::text("users") });
+
+    let mut app = Server::new();
+    app.use_router(lenient);
+    let lenient = serve(app);
+    let mut app = Server::new();
+    app.use_router(strict);
+    let strict = serve(app);
+
+    assert_eq!(get(lenient, "/users").status, 200);
+    assert_eq!(get(lenient, "/users/").status, 200);
+    assert_eq!(get(strict, "/users").status, 200);
+    assert_eq!(get(strict, "/users/").status, 404);
+}
+
+fn trailing_slash_app(mode: TrailingSlashMode) -> std::net::SocketAddr {
+    let mut strict = Router::new();
+    strict.strict_slash(true);
+    strict.get("/users", |_req| async { Response::text("users") });
+    strict.get("/docs/", |_req| async { Response::text("docs") });
+    let mut app = Server::new();
+    app.trailing_slash(mode);
+    app.use_router(strict);
+    app.get("/", |_req| async { Response::text("home") });
+    serve(app)
+}
+
+#[test]
+fn trailing_slash_redirects_before_routing() {
+    let addr = trailing_slash_app(TrailingSlashMode::RedirectToNoSlash);
+    let redirect = get(addr, "/users/?page=2");
+    assert_eq!(redirect.status, 301);
+    assert_eq!(redirect.header("Location"), Some("/users?page=2"));
+    assert_eq!(get(addr, "/users").text(), "users");
+    assert_eq!(get(addr, "/").text(), "home");
+
+    let addr = trailing_slash_app(TrailingSlashMode::RedirectToSlash);
+    let redirect = get(addr, "/docs");
+    assert_eq!(redirect.status, 301);
+    assert_eq!(redirect.header("Location"), Some("/docs/"));
+    assert_eq!(get(addr, "/docs/").text(), "docs");
+    assert_eq!(get(addr, "/").text(), "home");
+}
+
+#[test]
+fn transparent_trailing_slash_serves_both_forms() {
+    let addr = trailing_slash_app(TrailingSlashMode::Transparent);
+    assert_eq!(get(addr, "/users").text(), "users");
+    assert_eq!(get(addr, "/users/").text(), "users");
+    assert_eq!(get(addr, "/docs").text(), "docs");
+    assert_eq!(get(addr, "/docs/").text(), "docs");
+    assert_eq!(get(addr, "/missing/").status, 404);
+}
+
+#[test]
+fn chained_routers_are_tried_in_order_before_the_fallback() {
+    let mut users = Router::new();
+    users.get("/users", |_req| async { Response::text("users") });
+    users.get("/shared", |_req| async { Response::text("from users") });
+    let mut posts = Router::new();
+    posts.get("/posts", |_req| async { Response::text("posts") });
+    posts.get("/shared", |_req| async { Response::text("from posts") });
+
+    let mut app = Server::new();
+    app.get("/posts", |_req| async { Response::text("app posts") });
+    app.use_router(users);
+    app.use_router(posts);
+    app.fallback(|_req| async { Response::text("nothing here") });
+    let addr = serve(app);
+
+    assert_eq!(get(addr, "/users").text(), "users");
+    assert_eq!(get(addr, "/shared").text(), "from users");
+    assert_eq!(get(addr, "/posts").text(), "app posts");
+    let missing = get(addr, "/comments");
+    assert_eq!(missing.status, 200);
+    assert_eq!(missing.text(), "nothing here");
+}
+
+#[test]
+fn strict_slash_from_settings_applies_only_before_routes() {
+    let settings = || axeon::ServerSettings {
+        strict_slash: Some(true),
+        ..Default::default()
+    };
+
+    let mut app = Server::new();
+    app.configure(settings());
+    app.get("/users", |_req| async { Response::text("users") });
+    assert!(app.build().is_ok());
+    let addr = serve(app);
+    assert_eq!(get(addr, "/users").status, 200);
+    assert_eq!(get(addr, "/users/").status, 404);
+
+    let mut late = Server::new();
+    late.get("/users", |_req| async { Response::text("users") });
+    late.configure(settings());
+    let err = late.build().unwrap_err();
+    assert!(err.to_string().contains("strict_slash must be set before registering routes"));
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use axeon::Server;
+use common::{get, get_with, send_raw, serve};
+use std::path::{Path, PathBuf};
+
+// A fresh directory per test so parallel tests don't share files
+fn static_dir(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("axeon-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, contents) in files {
+        std::fs::write(dir.join(file), contents).unwrap();
+    }
+    dir
+}
+
+fn serve_dir(dir: &Path) -> std::net::SocketAddr {
+    let mut app = Server::new();
+    app.static_dir(dir.to_str().unwrap());
+    serve(app)
+}
+
+#[test]
+fn range_requests_on_large_files_are_streamed() {
+    let contents: Vec<u8> = (0..4_000_000u32).map(|i| (i % 251) as u8).collect();
+    let dir = static_dir("range", &[("photo.png", &contents)]);
+    let addr = serve_dir(&dir);
+
+    let response = get_with(addr, "/photo.png", "Range: bytes=1000000-1099999\r\n");
+    assert_eq!(response.status, 206);
+    assert_eq!(response.header("Content-Range"), Some("bytes 1000000-1099999/4000000"));
+    assert_eq!(response.header("Content-Type"), Some("image/png"));
+    assert_eq!(response.header("Transfer-Encoding"), Some("chunked"));
+    assert!(response.body == contents[1_000_000..1_100_000]);
+}
+
+#[test]
+fn range_kinds_are_resolved_against_the_file_size() {
+    let dir = static_dir("range-kinds", &[("digits.txt", b"0123456789")]);
+    let addr = serve_dir(&dir);
+    let range = |range: &str| get_with(addr, "/digits.txt", &format!("Range: {}\r\n", range));
+
+    let full = range("bytes=0-9");
+    assert_eq!(full.status, 206);
+    assert_eq!(full.header("Content-Range"), Some("bytes 0-9/10"));
+    assert_eq!(full.text(), "0123456789");
+
+    let suffix = range("bytes=-3");
+    assert_eq!(suffix.status, 206);
+    assert_eq!(suffix.header("Content-Range"), Some("bytes 7-9/10"));
+    assert_eq!(suffix.text(), "789");
+
+    let invalid = range("bytes=20-30");
+    assert_eq!(invalid.status, 416);
+    assert_eq!(invalid.header("Content-Range"), Some("bytes */10"));
+    assert_eq!(invalid.header("Content-Length"), Some("0"));
+}
+
+#[test]
+fn conditional_requests_are_answered_with_304() {
+    let dir = static_dir("conditional", &[("app.css", b"body { margin: 0 }")]);
+    let addr = serve_dir(&dir);
+
+    let first = get(addr, "/app.css");
+    assert_eq!(first.status, 200);
+    let etag = first.header("ETag").unwrap();
+    let last_modified = first.header("Last-Modified").unwrap();
+
+    let by_etag = format!("GET /app.css HTTP/1.1\r\nIf-None-Match: {}\r\nConnection: close\r\n\r\n", etag);
+    let raw = String::from_utf8(send_raw(addr, by_etag.as_bytes())).unwrap();
+    assert!(raw.starts_with("HTTP/1.1 304"));
+    assert!(raw.ends_with("\r\n\r\n"));
+
+    let by_date = get_with(addr, "/app.css", &format!("If-Modified-Since: {}\r\n", last_modified));
+    assert_eq!(by_date.status, 304);
+
+    let stale = get_with(addr, "/app.css", "If-None-Match: \"outdated\"\r\n");
+    assert_eq!(stale.status, 200);
+    assert_eq!(stale.text(), "body { margin: 0 }");
+}
+
+#[test]
+fn files_over_the_stream_threshold_are_sent_chunked() {
+    let contents: Vec<u8> = (0..10_000_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+    let dir = static_dir("stream", &[("archive.bin", &contents)]);
+    let mut app = Server::new();
+    app.static_dir(dir.to_str().unwrap());
+    app.static_stream_threshold(4 * 1024 * 1024);
+    let addr = serve(app);
+
+    let response = get(addr, "/archive.bin");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Transfer-Encoding"), Some("chunked"));
+    assert!(response.body == contents);
+}
+
+#[test]
+fn if_none_match_uses_weak_comparison() {
+    let dir = static_dir("weak-etag", &[("app.js", b"console.log(1)")]);
+    let addr = serve_dir(&dir);
+
+    let etag = get(addr, "/app.js").header("ETag").unwrap().to_string();
+    let opaque = etag.trim_start_matches("W/");
+    let if_none_match = |value: &str| get_with(addr, "/app.js", &format!("If-None-Match: {}\r\n", value)).status;
+
+    assert_eq!(if_none_match(opaque), 304);
+    assert_eq!(if_none_match(&format!("W/{}", opaque)), 304);
+    assert_eq!(if_none_match(&format!("\"other\", W/{}", opaque)), 304);
+    assert_eq!(if_none_match("*"), 304);
+    assert_eq!(if_none_match("W/\"other\""), 200);
+}
+
+#[test]
+fn head_responses_have_no_body_on_a_kept_alive_connection() {
+    let dir = static_dir("head", &[("a.txt", b"STATICBODY")]);
+    let mut app = Server::new();
+    app.static_dir(dir.to_str().unwrap());
+    app.get("/ok", |_req| async { axeon::Response::text("ok") });
+    let addr = serve(app);
+
+    let raw = send_raw(
+        addr,
+        b"HEAD /a.txt HTTP/1.1\r\n\r\nHEAD /missing HTTP/1.1\r\n\r\nHEAD /ok HTTP/1.1\r\n\r\nGET /ok HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    let raw = String::from_utf8(raw).unwrap();
+    let responses: Vec<&str> = raw.split("HTTP/1.1 ").skip(1).collect();
+    assert_eq!(responses.len(), 4, "{}", raw);
+
+    // Each HEAD response ends with its headers and keeps the GET length
+    let (file, missing, head_ok, ok) = (responses[0], responses[1], responses[2], responses[3]);
+    assert!(file.starts_with("200") && file.ends_with("\r\n\r\n"), "{:?}", file);
+    assert!(file.contains("Content-Length: 10\r\n"));
+    assert!(missing.starts_with("404") && missing.ends_with("\r\n\r\n"), "{:?}", missing);
+    assert!(!missing.contains("Content-Length: 0\r\n"));
+    assert!(head_ok.starts_with("200") && head_ok.ends_with("\r\n\r\n"), "{:?}", head_ok);
+    assert!(head_ok.contains("Content-Length: 2\r\n"));
+    assert!(ok.starts_with("200") && ok.ends_with("\r\n\r\nok"), "{:?}", ok);
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "tls")]
+
+mod common;
+
+use axeon::{Response, Server, ServerError};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// Certificates signed by tests/fixtures/ca.pem, valid until 2126
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn certs(name: &str) -> Vec<CertificateDer<'static>> {
+    let mut reader = BufReader::new(std::fs::File::open(fixture(name)).unwrap());
+    rustls_pemfile::certs(&mut reader).map(Result::unwrap).collect()
+}
+
+fn key(name: &str) -> PrivateKeyDer<'static> {
+    let mut reader = BufReader::new(std::fs::File::open(fixture(name)).unwrap());
+    rustls_pemfile::private_key(&mut reader).unwrap().unwrap()
+}
+
+fn mtls_server() -> SocketAddr {
+    let mut app = Server::new();
+    app.with_tls(fixture("server.pem"), fixture("server-key.pem"))
+        .with_client_auth(fixture("ca.pem"));
+    app.get("/whoami", |req| async move {
+        match req.client_cert.and_then(|cert| cert.common_name) {
+            Some(name) => Response::text(name),
+            None => Err(ServerError::Forbidden("No client certificate".to_string())),
+        }
+    });
+    common::serve(app)
+}
+
+// Sends a GET over TLS and returns everything read until the connection ends
+fn https_get(addr: SocketAddr, client_cert: bool) -> std::io::Result<String> {
+    let mut roots = RootCertStore::empty();
+    roots.add(certs("ca.pem").remove(0)).unwrap();
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+    let config = if client_cert {
+        builder.with_client_auth_cert(certs("client.pem"), key("client-key.pem")).unwrap()
+    } else {
+        builder.with_no_client_auth()
+    };
+
+    let name = ServerName::try_from("localhost").unwrap();
+    let connection = ClientConnection::new(Arc::new(config), name).unwrap();
+    let mut stream = StreamOwned::new(connection, TcpStream::connect(addr)?);
+    stream.write_all(b"GET /whoami HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+    let mut raw = Vec::new();
+    match stream.read_to_end(&mut raw) {
+        // The server closes the socket without a close_notify alert
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof && !raw.is_empty() => {}
+        result => {
+            result?;
+        }
+    }
+    Ok(String::from_utf8(raw).unwrap())
+}
+
+#[test]
+fn client_certificates_are_verified_and_exposed() {
+    let addr = mtls_server();
+
+    let raw = https_get(addr, true).unwrap();
+    assert!(raw.starts_with("HTTP/1.1 200"));
+    assert!(raw.ends_with("\r\n\r\nclient"));
+}
+
+#[test]
+fn clients_without_a_certificate_are_rejected() {
+    let addr = mtls_server();
+
+    // The handshake fails, so no HTTP response is ever sent
+    if let Ok(raw) = https_get(addr, false) {
+        assert!(raw.is_empty(), "got a response: {}", raw);
+    }
+}
+
+#[test]
+fn client_auth_without_tls_is_a_setup_error() {
+    let mut app = Server::new();
+    app.with_client_auth(fixture("ca.pem"));
+
+    let err = app.build().unwrap_err();
+    assert!(err.to_string().contains("with_client_auth requires with_tls"));
+    assert!(app.listen("127.0.0.1:0").is_err());
+}